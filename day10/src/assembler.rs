@@ -0,0 +1,480 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+/// Index of a register in the CPU's register file. `x` is always register 0.
+pub type Register = usize;
+
+pub const X: Register = 0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operand {
+    Register(Register),
+    Value(i32),
+}
+
+/// A CPU instruction. Jump offsets are relative to the jumping instruction, the assembler
+/// resolves labels into such offsets.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Instruction {
+    Noop,
+    Add(Register, Operand),
+    Sub(Register, Operand),
+    Mul(Register, Operand),
+    Set(Register, Operand),
+    Jmp(i32),
+    /// Jumps if the operand is not zero.
+    Jnz(Operand, i32),
+}
+
+impl Instruction {
+    /// The number of cycles the instruction takes. Its effect becomes visible once the last of
+    /// them has completed.
+    pub fn cycles(&self) -> u32 {
+        match self {
+            Instruction::Noop => 1,
+            Instruction::Add(..) | Instruction::Sub(..) => 2,
+            Instruction::Mul(..) => 4,
+            Instruction::Set(..) => 1,
+            Instruction::Jmp(_) => 1,
+            Instruction::Jnz(..) => 2,
+        }
+    }
+}
+
+/// An assembled program together with the names of the registers it uses, `x` being the first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Program {
+    pub instructions: Vec<Instruction>,
+    pub registers: Vec<String>,
+}
+
+impl Program {
+    pub fn register(&self, name: &str) -> Option<Register> {
+        self.registers.iter().position(|register| register == name)
+    }
+
+    /// The instruction in assembler syntax, with jump targets as relative offsets.
+    pub fn disassemble(&self, instruction: &Instruction) -> String {
+        let operand = |operand: &Operand| match operand {
+            Operand::Register(register) => self.registers[*register].clone(),
+            Operand::Value(value) => value.to_string(),
+        };
+        let arithmetic = |mnemonic: &str, register: Register, value: &Operand| {
+            if register == X && mnemonic != "set" {
+                format!("{}x {}", mnemonic, operand(value))
+            } else {
+                format!(
+                    "{} {} {}",
+                    mnemonic,
+                    self.registers[register],
+                    operand(value)
+                )
+            }
+        };
+
+        match instruction {
+            Instruction::Noop => String::from("noop"),
+            Instruction::Add(register, value) => arithmetic("add", *register, value),
+            Instruction::Sub(register, value) => arithmetic("sub", *register, value),
+            Instruction::Mul(register, value) => arithmetic("mul", *register, value),
+            Instruction::Set(register, value) => arithmetic("set", *register, value),
+            Instruction::Jmp(offset) => format!("jmp {:+}", offset),
+            Instruction::Jnz(value, offset) => format!("jnz {} {:+}", operand(value), offset),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseErrorKind {
+    UnknownMnemonic(String),
+    OperandCount {
+        mnemonic: String,
+        expected: usize,
+        found: usize,
+    },
+    InvalidNumber(String),
+    InvalidRegister(String),
+    InvalidLabel(String),
+    UnknownLabel(String),
+    DuplicateLabel(String),
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseErrorKind::UnknownMnemonic(mnemonic) => {
+                write!(f, "unknown mnemonic '{}'", mnemonic)
+            }
+            ParseErrorKind::OperandCount {
+                mnemonic,
+                expected,
+                found,
+            } => write!(
+                f,
+                "'{}' takes {} operand(s), found {}",
+                mnemonic, expected, found
+            ),
+            ParseErrorKind::InvalidNumber(number) => write!(f, "invalid number '{}'", number),
+            ParseErrorKind::InvalidRegister(name) => write!(f, "invalid register name '{}'", name),
+            ParseErrorKind::InvalidLabel(label) => write!(f, "invalid label '{}'", label),
+            ParseErrorKind::UnknownLabel(label) => write!(f, "unknown label '{}'", label),
+            ParseErrorKind::DuplicateLabel(label) => write!(f, "label '{}' defined twice", label),
+        }
+    }
+}
+
+/// Where and why a program could not be assembled. Lines and columns start at 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl ParseError {
+    /// The error followed by the offending source line with a caret below the column.
+    pub fn diagnostic(&self, source: &str) -> String {
+        let line = source.lines().nth(self.line - 1).unwrap_or_default();
+        format!("{}\n{}\n{:>width$}", self, line, "^", width = self.column)
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.kind
+        )
+    }
+}
+
+impl Error for ParseError {}
+
+/// A word of the source with its 1-based line and column.
+#[derive(Clone, Copy)]
+struct Token<'a> {
+    text: &'a str,
+    line: usize,
+    column: usize,
+}
+
+impl Token<'_> {
+    fn error(&self, kind: ParseErrorKind) -> ParseError {
+        ParseError {
+            line: self.line,
+            column: self.column,
+            kind,
+        }
+    }
+
+    fn label_definition(&self) -> Option<&str> {
+        self.text.strip_suffix(':')
+    }
+}
+
+/// Assembles source text with one instruction per line:
+///
+/// - `noop`
+/// - `addx <v>`, `subx <v>`, `mulx <v>` to add, subtract or multiply register `x` by `<v>`
+/// - `add <r> <v>`, `sub <r> <v>`, `mul <r> <v>`, `set <r> <v>` for any named register `<r>`
+/// - `jmp <target>` and `jnz <v> <target>` to jump unless `<v>` is zero
+///
+/// A value `<v>` is a number or a register name of lowercase letters, a jump target a relative
+/// offset like `-2` or `+3`, or a label. Labels are defined on lines of their own as `<label>:`.
+/// Everything after `#` or `;` is a comment.
+pub fn assemble(source: &str) -> Result<Program, ParseError> {
+    let lines: Vec<Vec<Token>> = source
+        .lines()
+        .enumerate()
+        .map(|(idx, line)| {
+            let code = line.split(['#', ';']).next().unwrap();
+            code.split_whitespace()
+                .map(|text| Token {
+                    text,
+                    line: idx + 1,
+                    column: text.as_ptr() as usize - line.as_ptr() as usize + 1,
+                })
+                .collect::<Vec<_>>()
+        })
+        .filter(|tokens| !tokens.is_empty())
+        .collect();
+
+    let mut labels = HashMap::new();
+    let mut instruction_count = 0;
+    for tokens in &lines {
+        match tokens.as_slice() {
+            [token] if token.label_definition().is_some() => {
+                let label = token.label_definition().unwrap();
+                if !is_name(label) {
+                    return Err(token.error(ParseErrorKind::InvalidLabel(label.to_string())));
+                }
+                if labels.insert(label, instruction_count).is_some() {
+                    return Err(token.error(ParseErrorKind::DuplicateLabel(label.to_string())));
+                }
+            }
+            _ => instruction_count += 1,
+        }
+    }
+
+    let mut assembler = Assembler {
+        labels,
+        registers: vec![String::from("x")],
+    };
+    let mut instructions = Vec::with_capacity(instruction_count);
+    for tokens in &lines {
+        if tokens.len() == 1 && tokens[0].label_definition().is_some() {
+            continue;
+        }
+
+        instructions.push(assembler.instruction(tokens, instructions.len())?);
+    }
+
+    Ok(Program {
+        instructions,
+        registers: assembler.registers,
+    })
+}
+
+fn is_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_lowercase())
+}
+
+struct Assembler<'a> {
+    labels: HashMap<&'a str, usize>,
+    registers: Vec<String>,
+}
+
+impl Assembler<'_> {
+    fn instruction(&mut self, tokens: &[Token], address: usize) -> Result<Instruction, ParseError> {
+        let (mnemonic, operands) = tokens.split_first().unwrap();
+        let expected = match mnemonic.text {
+            "noop" => 0,
+            "addx" | "subx" | "mulx" | "jmp" => 1,
+            "add" | "sub" | "mul" | "set" | "jnz" => 2,
+            _ => {
+                return Err(
+                    mnemonic.error(ParseErrorKind::UnknownMnemonic(mnemonic.text.to_string()))
+                )
+            }
+        };
+        if operands.len() != expected {
+            // Point at the first surplus operand, or at the mnemonic if operands are missing.
+            let token = operands.get(expected).unwrap_or(mnemonic);
+            return Err(token.error(ParseErrorKind::OperandCount {
+                mnemonic: mnemonic.text.to_string(),
+                expected,
+                found: operands.len(),
+            }));
+        }
+
+        let instruction = match (mnemonic.text, operands) {
+            ("noop", []) => Instruction::Noop,
+            ("addx", [value]) => Instruction::Add(X, self.operand(value)?),
+            ("subx", [value]) => Instruction::Sub(X, self.operand(value)?),
+            ("mulx", [value]) => Instruction::Mul(X, self.operand(value)?),
+            ("add", [register, value]) => {
+                Instruction::Add(self.register(register)?, self.operand(value)?)
+            }
+            ("sub", [register, value]) => {
+                Instruction::Sub(self.register(register)?, self.operand(value)?)
+            }
+            ("mul", [register, value]) => {
+                Instruction::Mul(self.register(register)?, self.operand(value)?)
+            }
+            ("set", [register, value]) => {
+                Instruction::Set(self.register(register)?, self.operand(value)?)
+            }
+            ("jmp", [target]) => Instruction::Jmp(self.offset(target, address)?),
+            ("jnz", [value, target]) => {
+                Instruction::Jnz(self.operand(value)?, self.offset(target, address)?)
+            }
+            _ => unreachable!("operand counts are checked above"),
+        };
+
+        Ok(instruction)
+    }
+
+    fn register(&mut self, token: &Token) -> Result<Register, ParseError> {
+        if !is_name(token.text) {
+            return Err(token.error(ParseErrorKind::InvalidRegister(token.text.to_string())));
+        }
+
+        match self
+            .registers
+            .iter()
+            .position(|register| register == token.text)
+        {
+            Some(register) => Ok(register),
+            None => {
+                self.registers.push(token.text.to_string());
+                Ok(self.registers.len() - 1)
+            }
+        }
+    }
+
+    fn operand(&mut self, token: &Token) -> Result<Operand, ParseError> {
+        if is_number_like(token.text) {
+            self.number(token).map(Operand::Value)
+        } else {
+            self.register(token).map(Operand::Register)
+        }
+    }
+
+    fn number(&self, token: &Token) -> Result<i32, ParseError> {
+        token
+            .text
+            .strip_prefix('+')
+            .unwrap_or(token.text)
+            .parse()
+            .map_err(|_| token.error(ParseErrorKind::InvalidNumber(token.text.to_string())))
+    }
+
+    fn offset(&self, target: &Token, address: usize) -> Result<i32, ParseError> {
+        if is_number_like(target.text) {
+            return self.number(target);
+        }
+        if !is_name(target.text) {
+            return Err(target.error(ParseErrorKind::InvalidLabel(target.text.to_string())));
+        }
+
+        self.labels
+            .get(target.text)
+            .map(|label_address| *label_address as i32 - address as i32)
+            .ok_or_else(|| target.error(ParseErrorKind::UnknownLabel(target.text.to_string())))
+    }
+}
+
+fn is_number_like(token: &str) -> bool {
+    token.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '+')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assemble_puzzle_program() {
+        let program = assemble("noop\naddx 3\naddx -5\n").unwrap();
+
+        assert_eq!(
+            vec![
+                Instruction::Noop,
+                Instruction::Add(X, Operand::Value(3)),
+                Instruction::Add(X, Operand::Value(-5)),
+            ],
+            program.instructions
+        );
+        assert_eq!(vec!["x"], program.registers);
+    }
+
+    #[test]
+    fn test_assemble_labels_registers_and_comments() {
+        let source = "\
+            # Multiplies x by 2, three times.
+            set n 3
+            loop:
+                mulx 2      ; doubles x
+                sub n 1
+                jnz n loop
+            jmp +1
+            subx n
+        ";
+
+        let program = assemble(source).unwrap();
+
+        assert_eq!(
+            vec![
+                Instruction::Set(1, Operand::Value(3)),
+                Instruction::Mul(X, Operand::Value(2)),
+                Instruction::Sub(1, Operand::Value(1)),
+                Instruction::Jnz(Operand::Register(1), -2),
+                Instruction::Jmp(1),
+                Instruction::Sub(X, Operand::Register(1)),
+            ],
+            program.instructions
+        );
+        assert_eq!(Some(1), program.register("n"));
+    }
+
+    #[test]
+    fn test_disassemble_reassembles() {
+        let source = "set n 3\nmulx 2\nsub n 1\njnz n -2\njmp +1\nadd n x\nnoop\n";
+        let program = assemble(source).unwrap();
+
+        let disassembly: Vec<String> = program
+            .instructions
+            .iter()
+            .map(|instruction| program.disassemble(instruction))
+            .collect();
+
+        assert_eq!(source, disassembly.join("\n") + "\n");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |source: &str| assemble(source).unwrap_err();
+
+        assert_eq!(
+            ParseError {
+                line: 2,
+                column: 3,
+                kind: ParseErrorKind::UnknownMnemonic(String::from("adx")),
+            },
+            error("noop\n  adx 3\n")
+        );
+        assert_eq!(
+            ParseError {
+                line: 1,
+                column: 8,
+                kind: ParseErrorKind::OperandCount {
+                    mnemonic: String::from("addx"),
+                    expected: 1,
+                    found: 2
+                },
+            },
+            error("addx 3 4")
+        );
+        assert_eq!(
+            "line 1, column 1: 'jnz' takes 2 operand(s), found 1",
+            error("jnz x").to_string()
+        );
+        assert_eq!(
+            ParseErrorKind::InvalidNumber(String::from("3a")),
+            error("addx 3a").kind
+        );
+        assert_eq!(
+            ParseErrorKind::InvalidNumber(String::from("99999999999")),
+            error("addx 99999999999").kind
+        );
+        assert_eq!(
+            ParseErrorKind::InvalidRegister(String::from("X")),
+            error("add X 1").kind
+        );
+        assert_eq!(
+            ParseErrorKind::UnknownLabel(String::from("end")),
+            error("jmp end").kind
+        );
+        assert_eq!(
+            ParseErrorKind::DuplicateLabel(String::from("a")),
+            error("a:\nnoop\na:\n").kind
+        );
+        assert_eq!(
+            ParseErrorKind::InvalidLabel(String::from("L1")),
+            error("L1:\n").kind
+        );
+    }
+
+    #[test]
+    fn test_diagnostic() {
+        let source = "noop\naddx 1 # fine\n    mulx two!\n";
+
+        let err = assemble(source).unwrap_err();
+
+        assert_eq!(
+            "line 3, column 10: invalid register name 'two!'\n    mulx two!\n         ^",
+            err.diagnostic(source)
+        );
+    }
+}
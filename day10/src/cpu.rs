@@ -0,0 +1,391 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::assembler::{Instruction, Operand, Program, Register, X};
+use crate::observer::CycleObserver;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CpuState {
+    Processing,
+    Halted,
+}
+
+/// Why an instruction could not be executed. The CPU halts on the first error.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CpuError {
+    /// The instruction uses a register the program does not define.
+    UndefinedRegister {
+        program_counter: usize,
+        register: Register,
+    },
+    /// The result does not fit into a register.
+    Overflow { program_counter: usize, cycle: u32 },
+    /// The program was still running after the maximum number of cycles.
+    CycleLimit {
+        program_counter: usize,
+        max_cycles: u32,
+    },
+}
+
+impl fmt::Display for CpuError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CpuError::UndefinedRegister {
+                program_counter,
+                register,
+            } => write!(
+                f,
+                "instruction {} uses undefined register {}",
+                program_counter, register
+            ),
+            CpuError::Overflow {
+                program_counter,
+                cycle,
+            } => write!(
+                f,
+                "instruction {} overflowed in cycle {}",
+                program_counter, cycle
+            ),
+            CpuError::CycleLimit {
+                program_counter,
+                max_cycles,
+            } => write!(
+                f,
+                "still running at instruction {} after {} cycles",
+                program_counter, max_cycles
+            ),
+        }
+    }
+}
+
+impl Error for CpuError {}
+
+/// Runs a program cycle by cycle. An instruction occupies the CPU for as many cycles as it
+/// costs, and its effect only becomes visible after its last cycle. The CPU halts once the
+/// program counter leaves the program or an instruction fails.
+pub struct Cpu {
+    program: Program,
+    registers: Vec<i32>,
+    program_counter: usize,
+    cycle: u32,
+    remaining_cycles: u32,
+    max_cycles: Option<u32>,
+    error: Option<CpuError>,
+}
+
+impl Cpu {
+    /// Register `x` starts at 1, all others at 0.
+    pub fn new(program: Program) -> Cpu {
+        // `x` exists even if a hand-built program forgot to name it.
+        let mut registers = vec![0; program.registers.len().max(1)];
+        registers[X] = 1;
+
+        Cpu {
+            program,
+            registers,
+            program_counter: 0,
+            cycle: 1,
+            remaining_cycles: 0,
+            max_cycles: None,
+            error: None,
+        }
+    }
+
+    /// Halts with an error instead of executing more than `max_cycles` cycles, so that programs
+    /// that jump backwards cannot run forever.
+    pub fn with_max_cycles(mut self, max_cycles: u32) -> Cpu {
+        self.max_cycles = Some(max_cycles);
+        self
+    }
+
+    /// The cycle that is executed next, starting at 1.
+    pub fn cycle(&self) -> u32 {
+        self.cycle
+    }
+
+    pub fn x(&self) -> i32 {
+        self.registers[X]
+    }
+
+    pub fn registers(&self) -> &[i32] {
+        &self.registers
+    }
+
+    pub fn register(&self, name: &str) -> Option<i32> {
+        self.program
+            .register(name)
+            .map(|register| self.registers[register])
+    }
+
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    pub fn program_counter(&self) -> usize {
+        self.program_counter
+    }
+
+    /// The cycles left until the current instruction completes, 0 between instructions.
+    pub fn remaining_cycles(&self) -> u32 {
+        self.remaining_cycles
+    }
+
+    /// The error the CPU halted on, if any.
+    pub fn error(&self) -> Option<CpuError> {
+        self.error
+    }
+
+    pub fn state(&self) -> CpuState {
+        if self.error.is_some()
+            || self.remaining_cycles == 0 && self.program_counter >= self.program.instructions.len()
+        {
+            CpuState::Halted
+        } else {
+            CpuState::Processing
+        }
+    }
+
+    /// Executes a single cycle, fetching the next instruction if the previous one has
+    /// completed. Does nothing once halted.
+    pub fn tik(&mut self) -> Result<CpuState, CpuError> {
+        if self.state() == CpuState::Halted {
+            return Ok(CpuState::Halted);
+        }
+
+        if let Some(max_cycles) = self.max_cycles.filter(|&max| self.cycle > max) {
+            let err = CpuError::CycleLimit {
+                program_counter: self.program_counter,
+                max_cycles,
+            };
+            self.error = Some(err);
+            return Err(err);
+        }
+
+        let instruction = self.program.instructions[self.program_counter];
+        if self.remaining_cycles == 0 {
+            self.remaining_cycles = instruction.cycles();
+        }
+
+        self.cycle += 1;
+        self.remaining_cycles -= 1;
+        if self.remaining_cycles == 0 {
+            if let Err(err) = self.execute(instruction) {
+                self.error = Some(err);
+                return Err(err);
+            }
+        }
+
+        Ok(self.state())
+    }
+
+    /// Like [`Cpu::tik`], notifying the observers before and after the cycle.
+    pub fn tik_observed(
+        &mut self,
+        observers: &mut [&mut dyn CycleObserver],
+    ) -> Result<CpuState, CpuError> {
+        if self.state() == CpuState::Halted {
+            return Ok(CpuState::Halted);
+        }
+
+        let cycle = self.cycle;
+        observers
+            .iter_mut()
+            .for_each(|observer| observer.before_cycle(cycle, self));
+        let result = self.tik();
+        observers
+            .iter_mut()
+            .for_each(|observer| observer.after_cycle(cycle, self));
+
+        result
+    }
+
+    /// Runs until the program halts.
+    pub fn run(&mut self) -> Result<(), CpuError> {
+        self.run_observed(&mut [])
+    }
+
+    /// Runs until the program halts, notifying the observers around every cycle.
+    pub fn run_observed(
+        &mut self,
+        observers: &mut [&mut dyn CycleObserver],
+    ) -> Result<(), CpuError> {
+        while self.tik_observed(observers)? == CpuState::Processing {}
+        Ok(())
+    }
+
+    fn execute(&mut self, instruction: Instruction) -> Result<(), CpuError> {
+        let mut jump = 1;
+        match instruction {
+            Instruction::Noop => (),
+            Instruction::Add(register, operand) => {
+                self.apply(register, operand, i32::checked_add)?
+            }
+            Instruction::Sub(register, operand) => {
+                self.apply(register, operand, i32::checked_sub)?
+            }
+            Instruction::Mul(register, operand) => {
+                self.apply(register, operand, i32::checked_mul)?
+            }
+            Instruction::Set(register, operand) => {
+                self.apply(register, operand, |_, value| Some(value))?
+            }
+            Instruction::Jmp(offset) => jump = offset,
+            Instruction::Jnz(operand, offset) => {
+                if self.value(operand)? != 0 {
+                    jump = offset;
+                }
+            }
+        }
+
+        // A jump before the start of the program halts the CPU just like one past its end.
+        self.program_counter =
+            usize::try_from(self.program_counter as i64 + jump as i64).unwrap_or(usize::MAX);
+        Ok(())
+    }
+
+    fn apply(
+        &mut self,
+        register: Register,
+        operand: Operand,
+        operation: impl Fn(i32, i32) -> Option<i32>,
+    ) -> Result<(), CpuError> {
+        let value = self.value(operand)?;
+        let result =
+            operation(self.register_value(register)?, value).ok_or(CpuError::Overflow {
+                program_counter: self.program_counter,
+                cycle: self.cycle - 1,
+            })?;
+        self.registers[register] = result;
+        Ok(())
+    }
+
+    fn value(&self, operand: Operand) -> Result<i32, CpuError> {
+        match operand {
+            Operand::Register(register) => self.register_value(register),
+            Operand::Value(value) => Ok(value),
+        }
+    }
+
+    fn register_value(&self, register: Register) -> Result<i32, CpuError> {
+        self.registers
+            .get(register)
+            .copied()
+            .ok_or(CpuError::UndefinedRegister {
+                program_counter: self.program_counter,
+                register,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler::assemble;
+
+    #[test]
+    fn test_effects_become_visible_after_the_last_cycle() {
+        let mut cpu = Cpu::new(assemble("noop\naddx 3\naddx -5\n").unwrap());
+        let mut x_during_cycles = Vec::new();
+
+        while cpu.state() == CpuState::Processing {
+            x_during_cycles.push((cpu.cycle(), cpu.x()));
+            cpu.tik().unwrap();
+        }
+
+        assert_eq!(
+            vec![(1, 1), (2, 1), (3, 1), (4, 4), (5, 4)],
+            x_during_cycles
+        );
+        assert_eq!((6, -1), (cpu.cycle(), cpu.x()));
+    }
+
+    #[test]
+    fn test_loop_with_named_registers() {
+        let source = "\
+            set n 3
+            loop:
+                mulx 2
+                sub n 1
+                jnz n loop
+        ";
+        let mut cpu = Cpu::new(assemble(source).unwrap());
+
+        cpu.run().unwrap();
+
+        assert_eq!(8, cpu.x());
+        assert_eq!(Some(0), cpu.register("n"));
+        // One cycle to set n, then three rounds of 4 + 2 + 2 cycles.
+        assert_eq!(1 + 3 * 8 + 1, cpu.cycle());
+    }
+
+    #[test]
+    fn test_jump_before_the_start_halts() {
+        let mut cpu = Cpu::new(assemble("addx 1\njmp -5\naddx 1\n").unwrap());
+
+        cpu.run().unwrap();
+
+        assert_eq!(2, cpu.x());
+        assert_eq!(Ok(CpuState::Halted), cpu.tik());
+    }
+
+    #[test]
+    fn test_halts_on_errors() {
+        let mut cpu = Cpu::new(assemble("noop\nset x 2147483647\naddx 1\nnoop\n").unwrap());
+
+        assert_eq!(
+            Err(CpuError::Overflow {
+                program_counter: 2,
+                cycle: 4
+            }),
+            cpu.run()
+        );
+        assert_eq!(CpuState::Halted, cpu.state());
+        assert_eq!(Ok(CpuState::Halted), cpu.tik());
+        assert_eq!((5, 2147483647), (cpu.cycle(), cpu.x()));
+
+        let program = Program {
+            instructions: vec![Instruction::Add(X, Operand::Register(3))],
+            registers: vec![],
+        };
+        let mut cpu = Cpu::new(program);
+        assert_eq!(
+            Err(CpuError::UndefinedRegister {
+                program_counter: 0,
+                register: 3
+            }),
+            cpu.run()
+        );
+        assert!(cpu.error().is_some());
+    }
+
+    #[test]
+    fn test_cycle_limit() {
+        let program = assemble("addx 1\njmp -1\n").unwrap();
+        let mut cpu = Cpu::new(program.clone()).with_max_cycles(10);
+
+        assert_eq!(
+            Err(CpuError::CycleLimit {
+                program_counter: 0,
+                max_cycles: 10
+            }),
+            cpu.run()
+        );
+        assert_eq!(CpuState::Halted, cpu.state());
+        // Three rounds of 2 + 1 cycles and the first cycle of the fourth `addx`.
+        assert_eq!((11, 4), (cpu.cycle(), cpu.x()));
+
+        // A program that halts within the limit is not affected.
+        let mut cpu = Cpu::new(assemble("noop\naddx 3\n").unwrap()).with_max_cycles(3);
+        assert_eq!(Ok(()), cpu.run());
+        assert_eq!(4, cpu.cycle());
+    }
+
+    #[test]
+    fn test_empty_program_is_halted() {
+        let mut cpu = Cpu::new(assemble("# nothing to do\n").unwrap());
+
+        assert_eq!(CpuState::Halted, cpu.state());
+        assert_eq!(Ok(CpuState::Halted), cpu.tik());
+        assert_eq!(1, cpu.cycle());
+    }
+}
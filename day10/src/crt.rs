@@ -0,0 +1,358 @@
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::{Cpu, CpuState, CycleObserver, Program};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CrtPixel {
+    Lit,
+    Dark,
+}
+
+impl fmt::Display for CrtPixel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CrtPixel::Lit => write!(f, "#"),
+            CrtPixel::Dark => write!(f, "."),
+        }
+    }
+}
+
+/// What the CRT does once it has drawn its last pixel.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WrapMode {
+    /// Stops drawing.
+    Stop,
+    /// Continues at the top left, drawing over the previous frame.
+    Wrap,
+    /// Adds another row at the bottom.
+    Grow,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CrtConfig {
+    pub width_px: u32,
+    pub height_px: u32,
+    /// The sprite covers `sprite_width_px` pixels centered on `x`, extending one more pixel to
+    /// the right than to the left for even widths.
+    pub sprite_width_px: u32,
+    pub wrap_mode: WrapMode,
+}
+
+impl Default for CrtConfig {
+    /// The puzzle's 40x6 CRT with its 3 pixel wide sprite.
+    fn default() -> Self {
+        CrtConfig {
+            width_px: 40,
+            height_px: 6,
+            sprite_width_px: 3,
+            wrap_mode: WrapMode::Stop,
+        }
+    }
+}
+
+pub struct Crt {
+    config: CrtConfig,
+    current_pixel_pos_x: u32,
+    current_pixel_pos_y: u32,
+    display: Vec<Vec<CrtPixel>>,
+}
+
+impl Crt {
+    /// A CRT with the puzzle's sprite, which stops drawing once the display is full.
+    pub fn new(width_px: u32, height_px: u32) -> Crt {
+        Crt::with_config(CrtConfig {
+            width_px,
+            height_px,
+            ..CrtConfig::default()
+        })
+    }
+
+    pub fn with_config(config: CrtConfig) -> Crt {
+        assert!(
+            config.width_px > 0,
+            "A CRT needs to be at least 1 pixel wide"
+        );
+        let display =
+            vec![vec![CrtPixel::Dark; config.width_px as usize]; config.height_px as usize];
+        Crt {
+            config,
+            current_pixel_pos_x: 0,
+            current_pixel_pos_y: 0,
+            display,
+        }
+    }
+
+    pub fn width_px(&self) -> u32 {
+        self.config.width_px
+    }
+
+    /// The current height, which can exceed the configured one when growing.
+    pub fn height_px(&self) -> u32 {
+        self.display.len() as u32
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> Option<CrtPixel> {
+        self.display.get(y as usize)?.get(x as usize).copied()
+    }
+
+    pub fn draw(&mut self, cpu: &Cpu) {
+        let sprite_left = cpu.x() as i64 - (self.config.sprite_width_px as i64 - 1) / 2;
+        let draw_lit_range = sprite_left..sprite_left + self.config.sprite_width_px as i64;
+
+        let pixel = if draw_lit_range.contains(&(self.current_pixel_pos_x as i64)) {
+            CrtPixel::Lit
+        } else {
+            CrtPixel::Dark
+        };
+
+        if self.current_pixel_pos_y as usize == self.display.len() {
+            match self.config.wrap_mode {
+                WrapMode::Stop => return,
+                WrapMode::Wrap => self.current_pixel_pos_y = 0,
+                WrapMode::Grow => self
+                    .display
+                    .push(vec![CrtPixel::Dark; self.config.width_px as usize]),
+            }
+        }
+        if let Some(row) = self.display.get_mut(self.current_pixel_pos_y as usize) {
+            row[self.current_pixel_pos_x as usize] = pixel;
+        }
+
+        self.advance_pixel_pos();
+    }
+
+    fn advance_pixel_pos(&mut self) {
+        if self.current_pixel_pos_x == self.config.width_px - 1 {
+            self.current_pixel_pos_x = 0;
+            self.current_pixel_pos_y += 1;
+        } else {
+            self.current_pixel_pos_x += 1;
+        }
+    }
+
+    pub fn render(&self) -> String {
+        let mut rendering_result: String = String::new();
+
+        for (idx, row) in self.display.iter().enumerate() {
+            for pixel in row {
+                rendering_result.push_str(&pixel.to_string());
+            }
+            if idx != self.display.len() - 1 {
+                rendering_result.push('\n');
+            }
+        }
+
+        rendering_result
+    }
+
+    /// Binary PBM (P4) with every pixel enlarged to `scale`x`scale`, lit pixels being black.
+    pub fn write_pbm(&self, scale: u32, mut out: impl Write) -> io::Result<()> {
+        let (width, height) = self.scaled_size(scale);
+        write!(out, "P4\n{} {}\n", width, height)?;
+
+        let row_bytes = width.div_ceil(8);
+        let mut bits = vec![0u8; row_bytes * height];
+        for (idx, pixel) in self.scaled_pixels(scale).enumerate() {
+            let (x, y) = (idx % width, idx / width);
+            if pixel == CrtPixel::Lit {
+                bits[y * row_bytes + x / 8] |= 0x80 >> (x % 8);
+            }
+        }
+
+        out.write_all(&bits)
+    }
+
+    /// Binary PGM (P5) with every pixel enlarged to `scale`x`scale`, lit pixels being white.
+    pub fn write_pgm(&self, scale: u32, mut out: impl Write) -> io::Result<()> {
+        let (width, height) = self.scaled_size(scale);
+        write!(out, "P5\n{} {}\n255\n", width, height)?;
+
+        let pixels: Vec<u8> = self
+            .scaled_pixels(scale)
+            .map(|pixel| match pixel {
+                CrtPixel::Lit => 255,
+                CrtPixel::Dark => 0,
+            })
+            .collect();
+
+        out.write_all(&pixels)
+    }
+
+    fn scaled_size(&self, scale: u32) -> (usize, usize) {
+        (
+            (self.width_px() * scale) as usize,
+            (self.height_px() * scale) as usize,
+        )
+    }
+
+    fn scaled_pixels(&self, scale: u32) -> impl Iterator<Item = CrtPixel> + '_ {
+        let scale = scale as usize;
+        self.display
+            .iter()
+            .flat_map(move |row| std::iter::repeat_n(row, scale))
+            .flat_map(move |row| {
+                row.iter()
+                    .flat_map(move |pixel| std::iter::repeat_n(*pixel, scale))
+            })
+    }
+}
+
+/// The CRT draws one pixel during every cycle, lit if the sprite centered on `x` covers it.
+impl CycleObserver for Crt {
+    fn before_cycle(&mut self, _cycle: u32, cpu: &Cpu) {
+        self.draw(cpu);
+    }
+}
+
+/// Runs the program and writes the CRT after every cycle to `frame_<cycle>.pgm` in `dir`.
+/// Returns the number of frames, failing if the program does or if it is still running after
+/// `max_cycles` cycles.
+pub fn write_frames(
+    program: Program,
+    config: CrtConfig,
+    scale: u32,
+    max_cycles: Option<u32>,
+    dir: &Path,
+) -> io::Result<usize> {
+    fs::create_dir_all(dir)?;
+    let mut cpu = Cpu::new(program);
+    if let Some(max_cycles) = max_cycles {
+        cpu = cpu.with_max_cycles(max_cycles);
+    }
+    let mut crt = Crt::with_config(config);
+
+    let mut frame_count = 0;
+    while cpu.state() == CpuState::Processing {
+        let cycle = cpu.cycle();
+        cpu.tik_observed(&mut [&mut crt])
+            .map_err(io::Error::other)?;
+
+        let file = File::create(dir.join(format!("frame_{:06}.pgm", cycle)))?;
+        let mut out = BufWriter::new(file);
+        crt.write_pgm(scale, &mut out)?;
+        out.flush()?;
+        frame_count += 1;
+    }
+
+    Ok(frame_count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assemble, CpuError};
+
+    fn draw(source: &str, config: CrtConfig) -> Crt {
+        let mut crt = Crt::with_config(config);
+        Cpu::new(assemble(source).unwrap())
+            .run_observed(&mut [&mut crt])
+            .unwrap();
+        crt
+    }
+
+    #[test]
+    fn test_sprite_width() {
+        let config = CrtConfig {
+            width_px: 8,
+            height_px: 1,
+            sprite_width_px: 4,
+            ..CrtConfig::default()
+        };
+
+        let crt = draw("addx 5\nnoop\nnoop\nnoop\nnoop\nnoop\nnoop\n", config);
+
+        // x is 1 during the first two cycles, the sprite covering pixels 0 to 3, then 6,
+        // covering 5 to 8.
+        assert_eq!("##...###", crt.render());
+    }
+
+    #[test]
+    fn test_wrap_modes() {
+        let source = "noop\nnoop\nnoop\naddx 2\nnoop\n";
+        let config = |wrap_mode| CrtConfig {
+            width_px: 2,
+            height_px: 2,
+            sprite_width_px: 1,
+            wrap_mode,
+        };
+
+        // x is 1 during the first five cycles and 3 during the sixth.
+        assert_eq!(".#\n.#", draw(source, config(WrapMode::Stop)).render());
+        assert_eq!("..\n.#", draw(source, config(WrapMode::Wrap)).render());
+        assert_eq!(".#\n.#\n..", draw(source, config(WrapMode::Grow)).render());
+    }
+
+    #[test]
+    fn test_write_pbm_and_pgm() {
+        let config = CrtConfig {
+            width_px: 5,
+            height_px: 1,
+            ..CrtConfig::default()
+        };
+        let crt = draw("noop\nnoop\nnoop\nnoop\nnoop\n", config);
+        assert_eq!("###..", crt.render());
+
+        let mut pbm = Vec::new();
+        crt.write_pbm(2, &mut pbm).unwrap();
+        let mut want = b"P4\n10 2\n".to_vec();
+        want.extend_from_slice(&[0b1111_1100, 0b0000_0000, 0b1111_1100, 0b0000_0000]);
+        assert_eq!(want, pbm);
+
+        let mut pgm = Vec::new();
+        crt.write_pgm(1, &mut pgm).unwrap();
+        let mut want = b"P5\n5 1\n255\n".to_vec();
+        want.extend_from_slice(&[255, 255, 255, 0, 0]);
+        assert_eq!(want, pgm);
+    }
+
+    #[test]
+    fn test_write_frames() {
+        let dir = std::env::temp_dir().join(format!("day10-frames-{}", std::process::id()));
+
+        let frame_count = write_frames(
+            assemble(include_str!("../test.txt")).unwrap(),
+            CrtConfig::default(),
+            1,
+            Some(240),
+            &dir,
+        )
+        .unwrap();
+
+        assert_eq!(240, frame_count);
+        let last_frame = fs::read(dir.join("frame_000240.pgm")).unwrap();
+        let crt = draw(include_str!("../test.txt"), CrtConfig::default());
+        let mut want = Vec::new();
+        crt.write_pgm(1, &mut want).unwrap();
+        assert_eq!(want, last_frame);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_write_frames_stops_endless_programs() {
+        let dir = std::env::temp_dir().join(format!("day10-endless-{}", std::process::id()));
+
+        let err = write_frames(
+            assemble("noop\njmp -1\n").unwrap(),
+            CrtConfig::default(),
+            1,
+            Some(5),
+            &dir,
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            Some(&CpuError::CycleLimit {
+                program_counter: 1,
+                max_cycles: 5
+            }),
+            err.get_ref().and_then(|err| err.downcast_ref::<CpuError>())
+        );
+        assert_eq!(5, fs::read_dir(&dir).unwrap().count());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
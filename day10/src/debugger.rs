@@ -0,0 +1,590 @@
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufWriter, Write};
+
+use crate::{Cpu, CpuError, CpuState, Crt, CycleObserver, Program, Register};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    fn parse(symbol: &str) -> Option<Comparison> {
+        match symbol {
+            "==" => Some(Comparison::Equal),
+            "!=" => Some(Comparison::NotEqual),
+            "<" => Some(Comparison::Less),
+            "<=" => Some(Comparison::LessOrEqual),
+            ">" => Some(Comparison::Greater),
+            ">=" => Some(Comparison::GreaterOrEqual),
+            _ => None,
+        }
+    }
+
+    fn symbol(self) -> &'static str {
+        match self {
+            Comparison::Equal => "==",
+            Comparison::NotEqual => "!=",
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+            Comparison::Greater => ">",
+            Comparison::GreaterOrEqual => ">=",
+        }
+    }
+
+    fn holds(self, left: i32, right: i32) -> bool {
+        match self {
+            Comparison::Equal => left == right,
+            Comparison::NotEqual => left != right,
+            Comparison::Less => left < right,
+            Comparison::LessOrEqual => left <= right,
+            Comparison::Greater => left > right,
+            Comparison::GreaterOrEqual => left >= right,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Breakpoint {
+    /// Stops before the cycle is executed.
+    Cycle(u32),
+    /// Stops before the instruction at this index is started.
+    Instruction(usize),
+    /// Stops after the cycle in which the condition became true.
+    Register(Register, Comparison, i32),
+}
+
+/// A register breakpoint on a register the program does not define.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UndefinedRegisterError {
+    pub register: Register,
+}
+
+impl fmt::Display for UndefinedRegisterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "breakpoint uses undefined register {}", self.register)
+    }
+}
+
+impl Error for UndefinedRegisterError {}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StopReason {
+    /// The requested cycle or instruction has been executed.
+    Stepped,
+    /// The breakpoint with this index was hit.
+    Breakpoint(usize),
+    Halted,
+    /// The CPU halted because an instruction failed.
+    Error(CpuError),
+}
+
+/// One executed cycle with the registers before and after it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceEntry {
+    pub cycle: u32,
+    pub program_counter: usize,
+    pub registers_before: Vec<i32>,
+    pub registers_after: Vec<i32>,
+}
+
+/// Records every executed cycle.
+#[derive(Default)]
+pub struct ExecutionTrace {
+    entries: Vec<TraceEntry>,
+}
+
+impl ExecutionTrace {
+    pub fn entries(&self) -> &[TraceEntry] {
+        &self.entries
+    }
+
+    /// One line per cycle with the instruction being executed and the registers before and
+    /// after the cycle.
+    pub fn write(&self, program: &Program, mut out: impl Write) -> io::Result<()> {
+        for entry in &self.entries {
+            writeln!(
+                out,
+                "cycle {:>5} | {:>4}: {:<16} | {} -> {}",
+                entry.cycle,
+                entry.program_counter,
+                program.disassemble(&program.instructions[entry.program_counter]),
+                format_registers(program, &entry.registers_before),
+                format_registers(program, &entry.registers_after)
+            )?;
+        }
+        out.flush()
+    }
+}
+
+impl CycleObserver for ExecutionTrace {
+    fn before_cycle(&mut self, cycle: u32, cpu: &Cpu) {
+        self.entries.push(TraceEntry {
+            cycle,
+            program_counter: cpu.program_counter(),
+            registers_before: cpu.registers().to_vec(),
+            registers_after: Vec::new(),
+        });
+    }
+
+    fn after_cycle(&mut self, _cycle: u32, cpu: &Cpu) {
+        let entry = self.entries.last_mut().unwrap();
+        entry.registers_after = cpu.registers().to_vec();
+    }
+}
+
+/// Runs a program with a CRT attached under control of breakpoints, and records every cycle.
+pub struct Debugger {
+    cpu: Cpu,
+    crt: Crt,
+    trace: ExecutionTrace,
+    breakpoints: Vec<Breakpoint>,
+    // Whether `resume` already checked the breakpoints before the first cycle.
+    checked_start: bool,
+}
+
+impl Debugger {
+    pub fn new(program: Program) -> Debugger {
+        Debugger {
+            cpu: Cpu::new(program),
+            crt: Crt::new(40, 6),
+            trace: ExecutionTrace::default(),
+            breakpoints: Vec::new(),
+            checked_start: false,
+        }
+    }
+
+    pub fn cpu(&self) -> &Cpu {
+        &self.cpu
+    }
+
+    pub fn crt(&self) -> &Crt {
+        &self.crt
+    }
+
+    pub fn trace(&self) -> &ExecutionTrace {
+        &self.trace
+    }
+
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    /// Adds the breakpoint and returns its index. Fails if it watches a register the program
+    /// does not define.
+    pub fn add_breakpoint(
+        &mut self,
+        breakpoint: Breakpoint,
+    ) -> Result<usize, UndefinedRegisterError> {
+        if let Breakpoint::Register(register, _, _) = breakpoint {
+            if register >= self.cpu.program().registers.len() {
+                return Err(UndefinedRegisterError { register });
+            }
+        }
+        self.breakpoints.push(breakpoint);
+        Ok(self.breakpoints.len() - 1)
+    }
+
+    pub fn remove_breakpoint(&mut self, idx: usize) -> Option<Breakpoint> {
+        (idx < self.breakpoints.len()).then(|| self.breakpoints.remove(idx))
+    }
+
+    /// Executes a single cycle. A breakpoint hit by the last cycle is reported before the
+    /// program halts.
+    pub fn step_cycle(&mut self) -> StopReason {
+        if self.cpu.state() == CpuState::Halted {
+            return StopReason::Halted;
+        }
+
+        let registers_before = self.cpu.registers().to_vec();
+        let state = match self.cpu.tik_observed(&mut [&mut self.crt, &mut self.trace]) {
+            Ok(state) => state,
+            Err(err) => return StopReason::Error(err),
+        };
+
+        match (self.hit_breakpoint(&registers_before), state) {
+            (Some(idx), _) => StopReason::Breakpoint(idx),
+            (None, CpuState::Halted) => StopReason::Halted,
+            (None, CpuState::Processing) => StopReason::Stepped,
+        }
+    }
+
+    /// Executes cycles until the current instruction has completed.
+    pub fn step_instruction(&mut self) -> StopReason {
+        loop {
+            let reason = self.step_cycle();
+            if reason != StopReason::Stepped || self.cpu.remaining_cycles() == 0 {
+                return reason;
+            }
+        }
+    }
+
+    /// Executes cycles until a breakpoint is hit or the program halts. The first time, stops
+    /// right away at a breakpoint before the first cycle or instruction.
+    pub fn resume(&mut self) -> StopReason {
+        if !self.checked_start && self.cpu.cycle() == 1 && self.cpu.state() == CpuState::Processing
+        {
+            self.checked_start = true;
+            if let Some(idx) = self.hit_breakpoint(self.cpu.registers()) {
+                return StopReason::Breakpoint(idx);
+            }
+        }
+
+        loop {
+            let reason = self.step_cycle();
+            if reason != StopReason::Stepped {
+                return reason;
+            }
+        }
+    }
+
+    fn hit_breakpoint(&self, registers_before: &[i32]) -> Option<usize> {
+        self.breakpoints
+            .iter()
+            .position(|breakpoint| self.is_hit(breakpoint, registers_before))
+    }
+
+    fn is_hit(&self, breakpoint: &Breakpoint, registers_before: &[i32]) -> bool {
+        match *breakpoint {
+            Breakpoint::Cycle(cycle) => self.cpu.cycle() == cycle,
+            Breakpoint::Instruction(idx) => {
+                self.cpu.remaining_cycles() == 0 && self.cpu.program_counter() == idx
+            }
+            Breakpoint::Register(register, comparison, value) => {
+                comparison.holds(self.cpu.registers()[register], value)
+                    && !comparison.holds(registers_before[register], value)
+            }
+        }
+    }
+
+    /// Reads commands line by line until `quit` or the end of the input. See `help` for the
+    /// commands.
+    pub fn run_commands(&mut self, input: impl BufRead, mut out: impl Write) -> io::Result<()> {
+        write!(out, "(dbg) ")?;
+        out.flush()?;
+        for line in input.lines() {
+            if !self.execute_command(&line?, &mut out)? {
+                break;
+            }
+            write!(out, "(dbg) ")?;
+            out.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Executes a single command. Returns `false` once the debugger should quit.
+    pub fn execute_command(&mut self, command: &str, mut out: impl Write) -> io::Result<bool> {
+        let tokens: Vec<&str> = command.split_whitespace().collect();
+        match tokens.as_slice() {
+            [] => (),
+            ["step"] | ["s"] => {
+                let reason = self.step_cycle();
+                self.report_stop(reason, &mut out)?
+            }
+            ["step", count] | ["s", count] => match count.parse::<u32>() {
+                Ok(count) => {
+                    let mut reason = StopReason::Stepped;
+                    for _ in 0..count {
+                        reason = self.step_cycle();
+                        if reason != StopReason::Stepped {
+                            break;
+                        }
+                    }
+                    self.report_stop(reason, &mut out)?
+                }
+                Err(_) => writeln!(out, "Invalid cycle count '{}'", count)?,
+            },
+            ["next"] | ["n"] => {
+                let reason = self.step_instruction();
+                self.report_stop(reason, &mut out)?
+            }
+            ["continue"] | ["c"] => {
+                let reason = self.resume();
+                self.report_stop(reason, &mut out)?
+            }
+            ["break", condition @ ..] | ["b", condition @ ..] => {
+                match self.parse_breakpoint(condition) {
+                    Some(breakpoint) => {
+                        // Register names are resolved by the program, so they are always valid.
+                        let idx = self.add_breakpoint(breakpoint).unwrap();
+                        writeln!(out, "Breakpoint {}: {}", idx, self.describe(&breakpoint))?
+                    }
+                    None => writeln!(
+                        out,
+                        "Expected 'break cycle <n>', 'break instruction <idx>' or \
+                         'break <register> <==|!=|<|<=|>|>=> <value>'"
+                    )?,
+                }
+            }
+            ["delete", idx] | ["d", idx] => {
+                match idx.parse().ok().and_then(|idx| self.remove_breakpoint(idx)) {
+                    Some(breakpoint) => writeln!(out, "Deleted {}", self.describe(&breakpoint))?,
+                    None => writeln!(out, "No breakpoint {}", idx)?,
+                }
+            }
+            ["breakpoints"] => {
+                for (idx, breakpoint) in self.breakpoints.iter().enumerate() {
+                    writeln!(out, "{}: {}", idx, self.describe(breakpoint))?;
+                }
+            }
+            ["registers"] | ["r"] => self.print_position(&mut out)?,
+            ["crt"] => writeln!(out, "{}", self.crt.render())?,
+            ["trace", path] => {
+                let written = File::create(path)
+                    .and_then(|file| self.trace.write(self.cpu.program(), BufWriter::new(file)));
+                match written {
+                    Ok(()) => writeln!(
+                        out,
+                        "Wrote {} cycles to {}",
+                        self.trace.entries().len(),
+                        path
+                    )?,
+                    Err(err) => writeln!(out, "Could not write {}: {}", path, err)?,
+                }
+            }
+            ["quit"] | ["q"] => return Ok(false),
+            ["help"] | ["h"] => writeln!(out, "{}", HELP)?,
+            _ => writeln!(out, "Unknown command '{}', try 'help'", command.trim())?,
+        }
+
+        Ok(true)
+    }
+
+    fn parse_breakpoint(&self, condition: &[&str]) -> Option<Breakpoint> {
+        match condition {
+            ["cycle", cycle] => cycle.parse().ok().map(Breakpoint::Cycle),
+            ["instruction", idx] => idx.parse().ok().map(Breakpoint::Instruction),
+            [register, comparison, value] => Some(Breakpoint::Register(
+                self.cpu.program().register(register)?,
+                Comparison::parse(comparison)?,
+                value.parse().ok()?,
+            )),
+            _ => None,
+        }
+    }
+
+    fn describe(&self, breakpoint: &Breakpoint) -> String {
+        match breakpoint {
+            Breakpoint::Cycle(cycle) => format!("before cycle {}", cycle),
+            Breakpoint::Instruction(idx) => format!("before instruction {}", idx),
+            Breakpoint::Register(register, comparison, value) => format!(
+                "once {} {} {}",
+                self.cpu.program().registers[*register],
+                comparison.symbol(),
+                value
+            ),
+        }
+    }
+
+    fn report_stop(&self, reason: StopReason, mut out: impl Write) -> io::Result<()> {
+        match reason {
+            StopReason::Stepped => (),
+            StopReason::Breakpoint(idx) => writeln!(
+                out,
+                "Hit breakpoint {}: {}",
+                idx,
+                self.describe(&self.breakpoints[idx])
+            )?,
+            StopReason::Halted => writeln!(out, "Program halted")?,
+            StopReason::Error(err) => writeln!(out, "Program halted: {}", err)?,
+        }
+        self.print_position(out)
+    }
+
+    fn print_position(&self, mut out: impl Write) -> io::Result<()> {
+        let program = self.cpu.program();
+        let instruction = match program.instructions.get(self.cpu.program_counter()) {
+            Some(instruction) => program.disassemble(instruction),
+            None => String::from("<end of program>"),
+        };
+        writeln!(
+            out,
+            "cycle {} | {}: {} | {}",
+            self.cpu.cycle(),
+            self.cpu.program_counter(),
+            instruction,
+            format_registers(program, self.cpu.registers())
+        )
+    }
+}
+
+const HELP: &str = "\
+step|s [<n>]                 execute one or <n> cycles
+next|n                       execute until the current instruction has completed
+continue|c                   execute until a breakpoint is hit or the program halts
+break|b cycle <n>            stop before cycle <n>
+break|b instruction <idx>    stop before instruction <idx> is started
+break|b <reg> <op> <value>   stop once the comparison becomes true, <op> is one of
+                             == != < <= > >=
+delete|d <idx>               delete a breakpoint
+breakpoints                  list all breakpoints
+registers|r                  show the current cycle, instruction and registers
+crt                          show the CRT as drawn so far
+trace <file>                 write all executed cycles to <file>
+quit|q                       quit";
+
+fn format_registers(program: &Program, registers: &[i32]) -> String {
+    program
+        .registers
+        .iter()
+        .zip(registers)
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assemble;
+
+    #[test]
+    fn test_breakpoints_and_stepping() {
+        let mut debugger = Debugger::new(assemble(include_str!("../test.txt")).unwrap());
+        debugger.add_breakpoint(Breakpoint::Cycle(20)).unwrap();
+        debugger
+            .add_breakpoint(Breakpoint::Register(0, Comparison::Greater, 20))
+            .unwrap();
+        debugger
+            .add_breakpoint(Breakpoint::Instruction(100))
+            .unwrap();
+
+        // x first exceeds 20 at the end of cycle 18.
+        assert_eq!(StopReason::Breakpoint(1), debugger.resume());
+        assert_eq!((19, 21), (debugger.cpu().cycle(), debugger.cpu().x()));
+        // The puzzle's first sample: x is 21 during cycle 20.
+        assert_eq!(StopReason::Breakpoint(0), debugger.resume());
+        assert_eq!((20, 21), (debugger.cpu().cycle(), debugger.cpu().x()));
+        assert_eq!(19, debugger.trace().entries().len());
+        // x drops to 20 during cycle 21 and exceeds 20 again at the end of cycle 23.
+        assert_eq!(StopReason::Breakpoint(1), debugger.resume());
+        assert_eq!(24, debugger.cpu().cycle());
+
+        debugger.remove_breakpoint(1);
+        assert_eq!(StopReason::Breakpoint(1), debugger.resume());
+        assert_eq!(
+            (165, 100),
+            (debugger.cpu().cycle(), debugger.cpu().program_counter())
+        );
+
+        debugger.remove_breakpoint(1);
+        assert_eq!(StopReason::Stepped, debugger.step_cycle());
+        assert_eq!(StopReason::Halted, debugger.resume());
+        assert_eq!(240, debugger.trace().entries().len());
+    }
+
+    #[test]
+    fn test_step_instruction() {
+        let mut debugger = Debugger::new(assemble("noop\naddx 3\naddx -5\n").unwrap());
+
+        debugger.step_instruction();
+        assert_eq!((2, 1), (debugger.cpu().cycle(), debugger.cpu().x()));
+        debugger.step_instruction();
+        assert_eq!((4, 4), (debugger.cpu().cycle(), debugger.cpu().x()));
+    }
+
+    #[test]
+    fn test_commands() {
+        let mut debugger = Debugger::new(assemble("noop\naddx 3\naddx -5\n").unwrap());
+        let commands = "break x == 4\ncontinue\nstep\nregisters\nbogus\nquit\nstep\n";
+
+        let mut out = Vec::new();
+        debugger
+            .run_commands(commands.as_bytes(), &mut out)
+            .unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        let want = "\
+            (dbg) Breakpoint 0: once x == 4\n\
+            (dbg) Hit breakpoint 0: once x == 4\n\
+            cycle 4 | 2: addx -5 | x=4\n\
+            (dbg) cycle 5 | 2: addx -5 | x=4\n\
+            (dbg) cycle 5 | 2: addx -5 | x=4\n\
+            (dbg) Unknown command 'bogus', try 'help'\n\
+            (dbg) ";
+        assert_eq!(want, out);
+        // Nothing runs after quitting.
+        assert_eq!(5, debugger.cpu().cycle());
+    }
+
+    #[test]
+    fn test_stops_on_cpu_errors() {
+        let mut debugger = Debugger::new(assemble("set x 2147483647\naddx 1\nnoop\n").unwrap());
+
+        let mut out = Vec::new();
+        debugger.execute_command("continue", &mut out).unwrap();
+
+        assert_eq!(
+            "Program halted: instruction 1 overflowed in cycle 3\n\
+             cycle 4 | 1: addx 1 | x=2147483647\n",
+            String::from_utf8(out).unwrap()
+        );
+        assert_eq!(StopReason::Halted, debugger.step_cycle());
+    }
+
+    #[test]
+    fn test_rejects_breakpoints_on_undefined_registers() {
+        let mut debugger = Debugger::new(assemble(include_str!("../test.txt")).unwrap());
+
+        assert_eq!(
+            Err(UndefinedRegisterError { register: 1 }),
+            debugger.add_breakpoint(Breakpoint::Register(1, Comparison::Equal, 0))
+        );
+        assert!(debugger.breakpoints().is_empty());
+        assert_eq!(StopReason::Halted, debugger.resume());
+    }
+
+    #[test]
+    fn test_breakpoints_at_the_start_and_the_end() {
+        let mut debugger = Debugger::new(assemble("noop\naddx 3\n").unwrap());
+        debugger.add_breakpoint(Breakpoint::Cycle(1)).unwrap();
+        debugger.add_breakpoint(Breakpoint::Instruction(0)).unwrap();
+        debugger
+            .add_breakpoint(Breakpoint::Register(0, Comparison::Equal, 4))
+            .unwrap();
+
+        assert_eq!(StopReason::Breakpoint(0), debugger.resume());
+        assert_eq!(1, debugger.cpu().cycle());
+        // x becomes 4 in the last cycle, which halts the program.
+        assert_eq!(StopReason::Breakpoint(2), debugger.resume());
+        assert_eq!((4, 4), (debugger.cpu().cycle(), debugger.cpu().x()));
+        assert_eq!(StopReason::Halted, debugger.resume());
+        assert_eq!(StopReason::Halted, debugger.step_cycle());
+    }
+
+    #[test]
+    fn test_write_trace() {
+        let mut debugger = Debugger::new(assemble("noop\naddx 3\n").unwrap());
+        debugger.resume();
+
+        let mut trace = Vec::new();
+        debugger
+            .trace()
+            .write(debugger.cpu().program(), &mut trace)
+            .unwrap();
+
+        let want = "\
+cycle     1 |    0: noop             | x=1 -> x=1
+cycle     2 |    1: addx 3           | x=1 -> x=1
+cycle     3 |    1: addx 3           | x=1 -> x=4
+";
+        assert_eq!(want, String::from_utf8(trace).unwrap());
+
+        let missing_dir =
+            std::env::temp_dir().join(format!("day10-missing-{}", std::process::id()));
+        let command = format!("trace {}/trace.txt", missing_dir.display());
+        let mut out = Vec::new();
+        assert!(debugger.execute_command(&command, &mut out).unwrap());
+        assert!(String::from_utf8(out).unwrap().starts_with(&format!(
+            "Could not write {}/trace.txt: ",
+            missing_dir.display()
+        )));
+    }
+}
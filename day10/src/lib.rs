@@ -0,0 +1,68 @@
+mod assembler;
+mod cpu;
+mod crt;
+mod debugger;
+mod observer;
+mod ocr;
+
+pub use assembler::{
+    assemble, Instruction, Operand, ParseError, ParseErrorKind, Program, Register, X,
+};
+pub use cpu::{Cpu, CpuError, CpuState};
+pub use crt::{write_frames, Crt, CrtConfig, CrtPixel, WrapMode};
+pub use debugger::{
+    Breakpoint, Comparison, Debugger, ExecutionTrace, StopReason, TraceEntry,
+    UndefinedRegisterError,
+};
+pub use observer::{CycleObserver, CycleTrace, Probe, SignalSampler};
+pub use ocr::{read_text, OcrError, UnknownGlyph};
+
+pub fn calculate_sum_of_signal_strengths(input: &str, mut sampler: SignalSampler) -> i64 {
+    run_puzzle_program(input, &mut sampler);
+    sampler.sum().unwrap_or_else(|err| panic!("{}", err))
+}
+
+pub fn produce_crt_image(input: &str) -> String {
+    let mut crt = Crt::new(40, 6);
+    run_puzzle_program(input, &mut crt);
+    crt.render()
+}
+
+/// The puzzle's programs are known to be valid, anything else should use [`assemble`] and
+/// [`Cpu`] directly to handle errors.
+fn run_puzzle_program(input: &str, observer: &mut dyn CycleObserver) {
+    let program = assemble(input).unwrap_or_else(|err| panic!("{}", err.diagnostic(input)));
+    Cpu::new(program)
+        .run_observed(&mut [observer])
+        .unwrap_or_else(|err| panic!("{}", err));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_part1() {
+        let input = include_str!("../test.txt");
+
+        assert_eq!(
+            calculate_sum_of_signal_strengths(input, SignalSampler::puzzle()),
+            13140
+        );
+    }
+
+    #[test]
+    fn test_part2() {
+        let input = include_str!("../test.txt");
+
+        assert_eq!(
+            produce_crt_image(input),
+            r########"##..##..##..##..##..##..##..##..##..##..
+###...###...###...###...###...###...###.
+####....####....####....####....####....
+#####.....#####.....#####.....#####.....
+######......######......######......####
+#######.......#######.......#######....."########
+        );
+    }
+}
@@ -1,275 +1,130 @@
-use std::fmt;
-use std::fmt::Write;
-
-struct Cpu {
-    register_value: i32,
-    cycle: u32,
-    processing_duration: u32,
-    processing_instruction: Option<CpuInstruction>,
-}
-
-enum CpuState {
-    Processing,
-    Halted,
-}
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+use day10::{
+    assemble, read_text, write_frames, Cpu, Crt, CrtConfig, CycleObserver, CycleTrace, Debugger,
+    OcrError, Program, SignalSampler, WrapMode,
+};
+
+/// Runs the program once with the signal sampler and the CRT attached, and with `--trace` also
+/// prints the registers during every cycle. The CRT can be configured and exported, optionally
+/// scaled up, as a single image or as one frame per cycle:
+///
+/// `day10 [--trace] [--max-cycles <n>] [--crt-width <px>] [--crt-height <px>]
+/// [--sprite-width <px>] [--wrap stop|wrap|grow] [--scale <n>] [--pbm <file>] [--pgm <file>]
+/// [--frames <dir>]`
+///
+/// or debugs it, or another program, with commands read from stdin:
+///
+/// `day10 debug [<program file>]`
+fn main() {
+    let input = include_str!("../input.txt");
 
-impl Cpu {
-    fn new() -> Cpu {
-        Cpu {
-            register_value: 1,
-            cycle: 1,
-            processing_duration: 0,
-            processing_instruction: None,
-        }
+    if std::env::args().nth(1).as_deref() == Some("debug") {
+        let source = match std::env::args().nth(2) {
+            Some(path) => {
+                std::fs::read_to_string(&path).unwrap_or_else(|err| panic!("{}: {}", path, err))
+            }
+            None => input.to_string(),
+        };
+        Debugger::new(assemble_or_exit(&source))
+            .run_commands(std::io::stdin().lock(), std::io::stdout().lock())
+            .unwrap();
+        return;
     }
 
-    fn tik(&mut self) -> CpuState {
-        self.cycle += 1;
-        self.processing_duration -= 1;
-
-        if self.processing_duration == 0 {
-            match self.processing_instruction.as_ref().unwrap() {
-                CpuInstruction::Noop => (),
-                CpuInstruction::Addx(value) => {
-                    self.register_value += value;
+    let mut args = std::env::args().skip(1);
+    let mut trace = false;
+    let mut max_cycles: Option<u32> = None;
+    let mut config = CrtConfig::default();
+    let mut scale = 1;
+    let mut pbm_file: Option<String> = None;
+    let mut pgm_file: Option<String> = None;
+    let mut frames_dir: Option<String> = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--trace" => trace = true,
+            "--max-cycles" => max_cycles = Some(parse_number_arg(&arg, args.next())),
+            "--crt-width" => config.width_px = parse_number_arg(&arg, args.next()),
+            "--crt-height" => config.height_px = parse_number_arg(&arg, args.next()),
+            "--sprite-width" => config.sprite_width_px = parse_number_arg(&arg, args.next()),
+            "--wrap" => {
+                config.wrap_mode = match args.next().as_deref() {
+                    Some("stop") => WrapMode::Stop,
+                    Some("wrap") => WrapMode::Wrap,
+                    Some("grow") => WrapMode::Grow,
+                    _ => panic!("--wrap requires stop, wrap or grow"),
                 }
             }
-
-            self.processing_instruction = None;
-            CpuState::Halted
-        } else {
-            CpuState::Processing
+            "--scale" => scale = parse_number_arg(&arg, args.next()),
+            "--pbm" => pbm_file = Some(args.next().expect("--pbm requires a file")),
+            "--pgm" => pgm_file = Some(args.next().expect("--pgm requires a file")),
+            "--frames" => frames_dir = Some(args.next().expect("--frames requires a directory")),
+            _ => panic!("Unknown argument {}", arg),
         }
     }
 
-    fn add_instruction(&mut self, instruction: CpuInstruction) {
-        match instruction {
-            CpuInstruction::Noop => self.processing_duration = 1,
-            CpuInstruction::Addx(_) => self.processing_duration = 2,
-        }
-
-        self.processing_instruction = Some(instruction);
-    }
-}
-
-enum CpuInstruction {
-    Noop,
-    Addx(i32),
-}
-
-#[derive(Clone)]
-enum CrtPixel {
-    Lit,
-    Dark,
-}
-
-impl fmt::Display for CrtPixel {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            CrtPixel::Lit => write!(f, "#"),
-            CrtPixel::Dark => write!(f, "."),
-        }
+    let mut sampler = SignalSampler::puzzle();
+    let mut crt = Crt::with_config(config);
+    let mut observers: Vec<&mut dyn CycleObserver> = vec![&mut sampler, &mut crt];
+    let mut cycle_trace = CycleTrace;
+    if trace {
+        observers.push(&mut cycle_trace);
     }
-}
-
-struct Crt {
-    width_px: u32,
-    current_pixel_pos_x: u32,
-    current_pixel_pos_y: u32,
-    display: Vec<Vec<CrtPixel>>,
-}
-
-impl Crt {
-    fn new(width_px: u32, height_px: u32) -> Crt {
-        let display = vec![vec![CrtPixel::Dark; width_px as usize]; height_px as usize];
-        Crt {
-            width_px,
-            current_pixel_pos_x: 0,
-            current_pixel_pos_y: 0,
-            display,
-        }
+    let program = assemble_or_exit(input);
+    let mut cpu = Cpu::new(program.clone());
+    if let Some(max_cycles) = max_cycles {
+        cpu = cpu.with_max_cycles(max_cycles);
     }
-
-    fn draw(&mut self, cpu: &Cpu) {
-        let draw_lit_range = cpu.register_value - 1..=cpu.register_value + 1;
-
-        let pixel: CrtPixel;
-        if draw_lit_range.contains(&(self.current_pixel_pos_x as i32)) {
-            pixel = CrtPixel::Lit;
-        } else {
-            pixel = CrtPixel::Dark;
-        }
-
-        println!("Cycle {}, register value {}", cpu.cycle, cpu.register_value);
-        println!(
-            "Drawing pixel {} at {},{}",
-            &pixel, self.current_pixel_pos_x, self.current_pixel_pos_y
-        );
-
-        self.display[self.current_pixel_pos_y as usize][self.current_pixel_pos_x as usize] = pixel;
-
-        self.advance_pixel_pos();
+    if let Err(err) = cpu.run_observed(&mut observers) {
+        eprintln!("{}", err);
+        std::process::exit(1);
     }
 
-    fn advance_pixel_pos(&mut self) {
-        if self.current_pixel_pos_x == self.width_px - 1 {
-            self.current_pixel_pos_x = 0;
-            self.current_pixel_pos_y += 1;
-        } else {
-            self.current_pixel_pos_x += 1;
+    match sampler.sum() {
+        Ok(sum) => println!("Result of part 1: {}", sum),
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
         }
     }
-
-    fn render(&self) -> String {
-        let mut rendering_result: String = String::new();
-
-        for (idx, row) in self.display.iter().enumerate() {
-            for pixel in row {
-                rendering_result.push_str(&pixel.to_string());
-            }
-            if idx != self.display.len() - 1 {
-                write!(rendering_result, "\n").unwrap();
-            }
-        }
-
-        return rendering_result;
+    let image = crt.render();
+    match read_text(&image) {
+        Ok(text) => println!("Result of part 2: {}", text),
+        // Only the puzzle's 6 rows can be read as letters.
+        Err(OcrError::WrongHeight { .. }) => println!("Result of part 2:\n{}", image),
+        Err(err) => println!("Result of part 2:\n{}\n{}", image, err),
     }
-}
-
-struct SignalStrengthMeasure {
-    next_cycle: u32,
-    cycle_step_size: u32,
-}
 
-impl SignalStrengthMeasure {
-    fn new() -> SignalStrengthMeasure {
-        SignalStrengthMeasure {
-            next_cycle: 20,
-            cycle_step_size: 40,
-        }
+    if let Some(path) = pbm_file {
+        crt.write_pbm(scale, create_file(&path)).unwrap();
     }
-
-    fn next(&mut self) -> u32 {
-        let next = self.next_cycle;
-        self.next_cycle += self.cycle_step_size;
-        return next;
+    if let Some(path) = pgm_file {
+        crt.write_pgm(scale, create_file(&path)).unwrap();
     }
-}
-
-fn main() {
-    let input = include_str!("../input.txt");
-
-    let result_part_1 = calculate_sum_of_signal_strengths(input, SignalStrengthMeasure::new());
-    let result_part_2 = produce_crt_image(input);
-
-    println!("Result of part 1: {}", result_part_1);
-    println!("Result of part 2:\n{}", result_part_2);
-}
-
-fn calculate_sum_of_signal_strengths(input: &str, mut measure: SignalStrengthMeasure) -> i32 {
-    let mut total_signal_strength = 0;
-
-    let mut cpu = Cpu::new();
-
-    let mut next_cycle_measure = measure.next();
-    let mut cycle_signal_strength: i32;
-    for instr in input.lines().map(parse_instruction) {
-        cpu.add_instruction(instr);
-
-        loop {
-            let processing;
-
-            match cpu.tik() {
-                CpuState::Processing => processing = true,
-                CpuState::Halted => processing = false,
-            }
-
-            if cpu.cycle == next_cycle_measure {
-                cycle_signal_strength = cpu.register_value * next_cycle_measure as i32;
-
-                println!(
-                    "Measuring register value at cycle {}: {}",
-                    next_cycle_measure, cpu.register_value
-                );
-                println!(
-                    "Signal strength at cycle {}: {}",
-                    next_cycle_measure, cycle_signal_strength
-                );
-
-                total_signal_strength += cycle_signal_strength;
-                next_cycle_measure = measure.next();
-            }
-
-            if !processing {
-                break;
-            }
-        }
+    if let Some(dir) = frames_dir {
+        let frame_count =
+            write_frames(program, config, scale, max_cycles, Path::new(&dir)).unwrap();
+        println!("Wrote {} frames to {}", frame_count, dir);
     }
-
-    return total_signal_strength;
 }
 
-fn produce_crt_image(input: &str) -> String {
-    let mut cpu = Cpu::new();
-    let mut crt = Crt::new(40, 6);
-
-    for instr in input.lines().map(parse_instruction) {
-        cpu.add_instruction(instr);
-
-        loop {
-            let processing;
-
-            crt.draw(&cpu);
-
-            match cpu.tik() {
-                CpuState::Processing => processing = true,
-                CpuState::Halted => processing = false,
-            }
-
-            if !processing {
-                break;
-            }
-        }
-    }
-
-    return crt.render();
+/// Prints where the program is invalid instead of panicking.
+fn assemble_or_exit(source: &str) -> Program {
+    assemble(source).unwrap_or_else(|err| {
+        eprintln!("{}", err.diagnostic(source));
+        std::process::exit(1);
+    })
 }
 
-fn parse_instruction(line: &str) -> CpuInstruction {
-    let mut parts = line.split_whitespace();
-    let _instr = parts.next().unwrap();
-    match parts.next() {
-        Some(value) => CpuInstruction::Addx(value.parse().unwrap()),
-        None => CpuInstruction::Noop,
-    }
+fn parse_number_arg(option: &str, value: Option<String>) -> u32 {
+    value
+        .and_then(|value| value.parse().ok())
+        .unwrap_or_else(|| panic!("{} requires a number", option))
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_part1() {
-        let input = include_str!("../test.txt");
-
-        assert_eq!(
-            calculate_sum_of_signal_strengths(input, SignalStrengthMeasure::new()),
-            13140
-        );
-    }
-
-    #[test]
-    fn test_part2() {
-        let input = include_str!("../test.txt");
-
-        assert_eq!(
-            produce_crt_image(input),
-            r########"##..##..##..##..##..##..##..##..##..##..
-###...###...###...###...###...###...###.
-####....####....####....####....####....
-#####.....#####.....#####.....#####.....
-######......######......######......####
-#######.......#######.......#######....."########
-        );
-    }
+fn create_file(path: &str) -> BufWriter<File> {
+    BufWriter::new(File::create(path).unwrap_or_else(|err| panic!("{}: {}", path, err)))
 }
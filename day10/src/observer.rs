@@ -0,0 +1,159 @@
+use std::collections::BTreeSet;
+
+use crate::{Cpu, CpuError};
+
+/// Gets notified by [`Cpu::run_observed`] around every cycle. During a cycle, the registers
+/// still hold the values from before the cycle's instruction completes.
+pub trait CycleObserver {
+    /// Called during `cycle`, before it is executed.
+    fn before_cycle(&mut self, _cycle: u32, _cpu: &Cpu) {}
+
+    /// Called once `cycle` has been executed, with the registers after it.
+    fn after_cycle(&mut self, _cycle: u32, _cpu: &Cpu) {}
+}
+
+/// Samples the signal strength, the cycle number times `x`, during a set of cycles.
+pub struct SignalSampler {
+    cycles: BTreeSet<u32>,
+    samples: Vec<(u32, i64)>,
+    sum: Result<i64, CpuError>,
+}
+
+impl SignalSampler {
+    pub fn new(cycles: impl IntoIterator<Item = u32>) -> SignalSampler {
+        SignalSampler {
+            cycles: cycles.into_iter().collect(),
+            samples: Vec::new(),
+            sum: Ok(0),
+        }
+    }
+
+    /// The 20th cycle and every 40 cycles after that, up to the end of the 240 cycle CRT image.
+    pub fn puzzle() -> SignalSampler {
+        SignalSampler::new((20..=220).step_by(40))
+    }
+
+    /// The signal strength of every sampled cycle the program ran for, in cycle order.
+    pub fn samples(&self) -> &[(u32, i64)] {
+        &self.samples
+    }
+
+    /// Fails with the instruction and cycle of the first sample the sum overflowed at.
+    pub fn sum(&self) -> Result<i64, CpuError> {
+        self.sum
+    }
+}
+
+impl CycleObserver for SignalSampler {
+    fn before_cycle(&mut self, cycle: u32, cpu: &Cpu) {
+        if !self.cycles.contains(&cycle) {
+            return;
+        }
+
+        let overflow = CpuError::Overflow {
+            program_counter: cpu.program_counter(),
+            cycle,
+        };
+        let strength = i64::from(cycle).checked_mul(cpu.x().into());
+        if let Some(strength) = strength {
+            self.samples.push((cycle, strength));
+        }
+        self.sum = self.sum.and_then(|sum| {
+            strength
+                .and_then(|strength| sum.checked_add(strength))
+                .ok_or(overflow)
+        });
+    }
+}
+
+/// Prints the cycle number and all registers during every cycle.
+pub struct CycleTrace;
+
+impl CycleObserver for CycleTrace {
+    fn before_cycle(&mut self, cycle: u32, cpu: &Cpu) {
+        let registers: Vec<String> = cpu
+            .program()
+            .registers
+            .iter()
+            .zip(cpu.registers())
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect();
+        println!("Cycle {}: {}", cycle, registers.join(" "));
+    }
+}
+
+/// Turns a closure into an observer that is called during every cycle.
+pub struct Probe<F: FnMut(u32, &Cpu)>(pub F);
+
+impl<F: FnMut(u32, &Cpu)> CycleObserver for Probe<F> {
+    fn before_cycle(&mut self, cycle: u32, cpu: &Cpu) {
+        (self.0)(cycle, cpu);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assemble;
+
+    #[test]
+    fn test_signal_sampler_at_arbitrary_cycles() {
+        let mut cpu = Cpu::new(assemble("noop\naddx 3\naddx -5\n").unwrap());
+        let mut sampler = SignalSampler::new([5, 2, 9]);
+
+        cpu.run_observed(&mut [&mut sampler]).unwrap();
+
+        // Cycle 9 is never reached.
+        assert_eq!(&[(2, 2), (5, 20)], sampler.samples());
+        assert_eq!(Ok(22), sampler.sum());
+    }
+
+    #[test]
+    fn test_signal_sampler_does_not_overflow() {
+        let source = "set x 2147483647\nnoop\nnoop\nnoop\n";
+        let mut cpu = Cpu::new(assemble(source).unwrap());
+        let mut sampler = SignalSampler::new([3, 4]);
+
+        cpu.run_observed(&mut [&mut sampler]).unwrap();
+
+        assert_eq!(Ok(7 * 2147483647), sampler.sum());
+
+        // The sum only overflows after billions of samples, so start close to the limit.
+        let mut sampler = SignalSampler::new([1]);
+        sampler.sum = Ok(i64::MAX);
+        let mut cpu = Cpu::new(assemble(source).unwrap());
+        cpu.run_observed(&mut [&mut sampler]).unwrap();
+        assert_eq!(
+            Err(CpuError::Overflow {
+                program_counter: 0,
+                cycle: 1
+            }),
+            sampler.sum()
+        );
+    }
+
+    #[test]
+    fn test_observers_see_registers_before_and_after_each_cycle() {
+        struct Recorder(Vec<(u32, i32, i32)>);
+        impl CycleObserver for Recorder {
+            fn before_cycle(&mut self, cycle: u32, cpu: &Cpu) {
+                self.0.push((cycle, cpu.x(), 0));
+            }
+            fn after_cycle(&mut self, _cycle: u32, cpu: &Cpu) {
+                self.0.last_mut().unwrap().2 = cpu.x();
+            }
+        }
+        let mut recorder = Recorder(Vec::new());
+        let mut max_x = 0;
+        let mut probe = Probe(|_, cpu: &Cpu| max_x = max_x.max(cpu.x()));
+
+        let mut cpu = Cpu::new(assemble("noop\naddx 3\naddx -5\n").unwrap());
+        cpu.run_observed(&mut [&mut recorder, &mut probe]).unwrap();
+
+        assert_eq!(
+            vec![(1, 1, 1), (2, 1, 1), (3, 1, 4), (4, 4, 4), (5, 4, -1)],
+            recorder.0
+        );
+        assert_eq!(4, max_x);
+    }
+}
@@ -0,0 +1,194 @@
+use std::error::Error;
+use std::fmt;
+
+const GLYPH_WIDTH: usize = 4;
+const GLYPH_HEIGHT: usize = 6;
+/// Glyphs are separated by a single dark column.
+const GLYPH_SPACING: usize = 1;
+
+/// The letters that appear in Advent of Code's CRT puzzles, `#` being a lit pixel.
+const FONT: &[(char, [&str; GLYPH_HEIGHT])] = &[
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', [".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+    (' ', ["....", "....", "....", "....", "....", "...."]),
+];
+
+/// A glyph that is not in the font, with its position in the text and its rows.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnknownGlyph {
+    pub position: usize,
+    pub bitmap: Vec<String>,
+}
+
+/// Why an image could not be read.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OcrError {
+    /// The image is not as high as the glyphs.
+    WrongHeight { rows: usize },
+    /// The text as far as it could be read, with `?` for every unknown glyph.
+    UnknownGlyphs {
+        partial_text: String,
+        unknown_glyphs: Vec<UnknownGlyph>,
+    },
+}
+
+impl fmt::Display for OcrError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OcrError::WrongHeight { rows } => write!(
+                f,
+                "The image is {} rows high instead of {}",
+                rows, GLYPH_HEIGHT
+            ),
+            OcrError::UnknownGlyphs {
+                partial_text,
+                unknown_glyphs,
+            } => {
+                write!(f, "Could not read all of '{}'", partial_text)?;
+                for glyph in unknown_glyphs {
+                    write!(f, "\nUnknown glyph at position {}:", glyph.position)?;
+                    for row in &glyph.bitmap {
+                        write!(f, "\n{}", row)?;
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Error for OcrError {}
+
+/// Reads the letters of a rendered CRT image of `#` and `.`, 6 rows high, with one dark
+/// column between 4 column wide glyphs.
+pub fn read_text(image: &str) -> Result<String, OcrError> {
+    let rows: Vec<&[u8]> = image.lines().map(str::as_bytes).collect();
+    if rows.len() != GLYPH_HEIGHT {
+        return Err(OcrError::WrongHeight { rows: rows.len() });
+    }
+
+    let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+    let glyph_count = (width + GLYPH_SPACING) / (GLYPH_WIDTH + GLYPH_SPACING);
+
+    let mut text = String::with_capacity(glyph_count);
+    let mut unknown_glyphs = Vec::new();
+    for position in 0..glyph_count {
+        let left = position * (GLYPH_WIDTH + GLYPH_SPACING);
+        let bitmap: Vec<String> = rows
+            .iter()
+            .map(|row| {
+                (left..left + GLYPH_WIDTH)
+                    .map(|col| {
+                        if row.get(col) == Some(&b'#') {
+                            '#'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+
+        match FONT.iter().find(|(_, glyph)| *glyph == bitmap.as_slice()) {
+            Some((letter, _)) => text.push(*letter),
+            None => {
+                text.push('?');
+                unknown_glyphs.push(UnknownGlyph { position, bitmap });
+            }
+        }
+    }
+
+    if unknown_glyphs.is_empty() {
+        Ok(text)
+    } else {
+        Err(OcrError::UnknownGlyphs {
+            partial_text: text,
+            unknown_glyphs,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::produce_crt_image;
+
+    #[test]
+    fn test_read_input() {
+        let image = produce_crt_image(include_str!("../input.txt"));
+
+        assert_eq!(Ok(String::from("RBPARAGF")), read_text(&image));
+    }
+
+    #[test]
+    fn test_read_whole_font() {
+        let rows: Vec<String> = (0..GLYPH_HEIGHT)
+            .map(|row| {
+                FONT.iter()
+                    .map(|(_, glyph)| glyph[row])
+                    .collect::<Vec<_>>()
+                    .join(".")
+            })
+            .collect();
+
+        assert_eq!(
+            Ok(String::from("ABCEFGHIJKLOPRSUZ ")),
+            read_text(&rows.join("\n"))
+        );
+    }
+
+    #[test]
+    fn test_unknown_glyphs() {
+        let image = produce_crt_image(include_str!("../test.txt"));
+
+        let err = read_text(&image).unwrap_err();
+
+        let OcrError::UnknownGlyphs {
+            partial_text,
+            unknown_glyphs,
+        } = &err
+        else {
+            panic!("Unexpected error {:?}", err);
+        };
+        assert_eq!("????????", partial_text);
+        assert_eq!(8, unknown_glyphs.len());
+        assert_eq!(
+            vec!["##..", "###.", "####", "####", "####", "####"],
+            unknown_glyphs[0].bitmap
+        );
+        assert!(err
+            .to_string()
+            .starts_with("Could not read all of '????????'\nUnknown glyph at position 0:\n##..\n"));
+    }
+
+    #[test]
+    fn test_wrong_height() {
+        let image = produce_crt_image(include_str!("../input.txt"));
+        let five_rows: Vec<&str> = image.lines().take(5).collect();
+
+        assert_eq!(
+            Err(OcrError::WrongHeight { rows: 5 }),
+            read_text(&five_rows.join("\n"))
+        );
+        assert_eq!(Err(OcrError::WrongHeight { rows: 0 }), read_text(""));
+        assert_eq!(
+            "The image is 7 rows high instead of 6",
+            read_text(&format!("{}\n.", image)).unwrap_err().to_string()
+        );
+    }
+}
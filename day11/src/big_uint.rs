@@ -0,0 +1,268 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Mul};
+
+/// An arbitrary-precision unsigned integer, stored as 32 bit limbs with the least significant
+/// first and without leading zero limbs, so that zero has no limbs at all.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct BigUint {
+    limbs: Vec<u32>,
+}
+
+impl BigUint {
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    pub fn to_u64(&self) -> Option<u64> {
+        match self.limbs.as_slice() {
+            [] => Some(0),
+            [low] => Some(*low as u64),
+            [low, high] => Some((*high as u64) << 32 | *low as u64),
+            _ => None,
+        }
+    }
+
+    pub fn bit_len(&self) -> u64 {
+        match self.limbs.last() {
+            None => 0,
+            Some(top) => self.limbs.len() as u64 * 32 - top.leading_zeros() as u64,
+        }
+    }
+
+    fn bit(&self, idx: u64) -> bool {
+        self.limbs
+            .get((idx / 32) as usize)
+            .is_some_and(|limb| limb >> (idx % 32) & 1 == 1)
+    }
+
+    fn normalize(mut self) -> BigUint {
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+        self
+    }
+
+    pub fn checked_sub(&self, other: &BigUint) -> Option<BigUint> {
+        if *self < *other {
+            return None;
+        }
+        let mut limbs = Vec::with_capacity(self.limbs.len());
+        let mut borrow = 0;
+        for (idx, &limb) in self.limbs.iter().enumerate() {
+            let subtrahend = *other.limbs.get(idx).unwrap_or(&0) as u64 + borrow;
+            let (difference, underflow) = (limb as u64).overflowing_sub(subtrahend);
+            limbs.push(difference as u32);
+            borrow = underflow as u64;
+        }
+        Some(BigUint { limbs }.normalize())
+    }
+
+    /// The quotient and remainder, or `None` when dividing by zero.
+    pub fn div_rem(&self, divisor: &BigUint) -> Option<(BigUint, BigUint)> {
+        if divisor.is_zero() {
+            return None;
+        }
+        if let [small] = divisor.limbs.as_slice() {
+            let (quotient, remainder) = self.div_rem_u32(*small);
+            return Some((quotient, BigUint::from(remainder as u64)));
+        }
+
+        // Binary long division, bringing down one bit of the dividend at a time.
+        let mut quotient = vec![0; self.limbs.len()];
+        let mut remainder = BigUint::default();
+        for idx in (0..self.bit_len()).rev() {
+            remainder = remainder.shl1(self.bit(idx));
+            if remainder >= *divisor {
+                remainder = remainder.checked_sub(divisor).unwrap();
+                quotient[(idx / 32) as usize] |= 1 << (idx % 32);
+            }
+        }
+        Some((BigUint { limbs: quotient }.normalize(), remainder))
+    }
+
+    fn div_rem_u32(&self, divisor: u32) -> (BigUint, u32) {
+        let mut quotient = vec![0; self.limbs.len()];
+        let mut remainder = 0u64;
+        for (idx, &limb) in self.limbs.iter().enumerate().rev() {
+            let dividend = remainder << 32 | limb as u64;
+            quotient[idx] = (dividend / divisor as u64) as u32;
+            remainder = dividend % divisor as u64;
+        }
+        (BigUint { limbs: quotient }.normalize(), remainder as u32)
+    }
+
+    /// Panics if `divisor` is zero.
+    pub fn rem_u64(&self, divisor: u64) -> u64 {
+        assert!(divisor != 0, "Division by zero");
+        let remainder = self.limbs.iter().rev().fold(0u128, |remainder, &limb| {
+            (remainder << 32 | limb as u128) % divisor as u128
+        });
+        remainder as u64
+    }
+
+    /// Shifts left by one bit, shifting in `low_bit`.
+    fn shl1(mut self, low_bit: bool) -> BigUint {
+        let mut carry = low_bit as u32;
+        for limb in &mut self.limbs {
+            let next_carry = *limb >> 31;
+            *limb = *limb << 1 | carry;
+            carry = next_carry;
+        }
+        if carry != 0 {
+            self.limbs.push(carry);
+        }
+        self
+    }
+}
+
+impl From<u64> for BigUint {
+    fn from(value: u64) -> Self {
+        BigUint {
+            limbs: vec![value as u32, (value >> 32) as u32],
+        }
+        .normalize()
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.limbs
+            .len()
+            .cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Add for &BigUint {
+    type Output = BigUint;
+
+    fn add(self, other: &BigUint) -> BigUint {
+        let len = self.limbs.len().max(other.limbs.len());
+        let mut limbs = Vec::with_capacity(len + 1);
+        let mut carry = 0;
+        for idx in 0..len {
+            let sum = *self.limbs.get(idx).unwrap_or(&0) as u64
+                + *other.limbs.get(idx).unwrap_or(&0) as u64
+                + carry;
+            limbs.push(sum as u32);
+            carry = sum >> 32;
+        }
+        limbs.push(carry as u32);
+        BigUint { limbs }.normalize()
+    }
+}
+
+impl Mul for &BigUint {
+    type Output = BigUint;
+
+    /// Schoolbook multiplication.
+    fn mul(self, other: &BigUint) -> BigUint {
+        let mut limbs = vec![0u32; self.limbs.len() + other.limbs.len()];
+        for (i, &a) in self.limbs.iter().enumerate() {
+            let mut carry = 0u64;
+            for (j, &b) in other.limbs.iter().enumerate() {
+                let product = a as u64 * b as u64 + limbs[i + j] as u64 + carry;
+                limbs[i + j] = product as u32;
+                carry = product >> 32;
+            }
+            limbs[i + other.limbs.len()] = carry as u32;
+        }
+        BigUint { limbs }.normalize()
+    }
+}
+
+impl fmt::Display for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        const CHUNK: u32 = 1_000_000_000;
+
+        // Groups of 9 decimal digits, the least significant first.
+        let mut chunks = Vec::new();
+        let mut rest = self.clone();
+        while !rest.is_zero() {
+            let (quotient, remainder) = rest.div_rem_u32(CHUNK);
+            chunks.push(remainder);
+            rest = quotient;
+        }
+
+        match chunks.split_last() {
+            None => write!(f, "0"),
+            Some((most_significant, others)) => {
+                write!(f, "{}", most_significant)?;
+                for chunk in others.iter().rev() {
+                    write!(f, "{:09}", chunk)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arithmetic_matches_u128() {
+        let values: [u64; 6] = [0, 1, 7, 4_294_967_295, 4_294_967_296, u64::MAX];
+
+        for a in values {
+            for b in values {
+                let (big_a, big_b) = (BigUint::from(a), BigUint::from(b));
+                let (wide_a, wide_b) = (a as u128, b as u128);
+
+                assert_eq!((wide_a + wide_b).to_string(), (&big_a + &big_b).to_string());
+                assert_eq!((wide_a * wide_b).to_string(), (&big_a * &big_b).to_string());
+                assert_eq!(
+                    wide_a.checked_sub(wide_b).map(|d| d.to_string()),
+                    big_a.checked_sub(&big_b).map(|d| d.to_string())
+                );
+                assert_eq!(wide_a.cmp(&wide_b), big_a.cmp(&big_b));
+                if let Some(want_quotient) = a.checked_div(b) {
+                    let (quotient, remainder) = big_a.div_rem(&big_b).unwrap();
+                    assert_eq!(Some(want_quotient), quotient.to_u64());
+                    assert_eq!(Some(a % b), remainder.to_u64());
+                    assert_eq!(a % b, big_a.rem_u64(b));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_large_numbers() {
+        // 2^128 = 340282366920938463463374607431768211456
+        let two_to_64 = &BigUint::from(u64::MAX) + &BigUint::from(1);
+        let two_to_128 = &two_to_64 * &two_to_64;
+        assert_eq!(
+            "340282366920938463463374607431768211456",
+            two_to_128.to_string()
+        );
+        assert_eq!(129, two_to_128.bit_len());
+        assert_eq!(None, two_to_128.to_u64());
+
+        // 2^128 = 3 * 113427455640312821154458202477256070485 + 1
+        let (quotient, remainder) = two_to_128.div_rem(&BigUint::from(3)).unwrap();
+        assert_eq!(
+            "113427455640312821154458202477256070485",
+            quotient.to_string()
+        );
+        assert_eq!(BigUint::from(1), remainder);
+
+        let (quotient, remainder) = two_to_128.div_rem(&two_to_64).unwrap();
+        assert_eq!(two_to_64, quotient);
+        assert!(remainder.is_zero());
+        assert_eq!(
+            Some(BigUint::from(u64::MAX)),
+            two_to_64.checked_sub(&BigUint::from(1))
+        );
+        // 2^11 = 89 * 23 + 1, so 2^128 = 2^(11 * 11 + 7) leaves the same remainder as 2^7.
+        assert_eq!(128 % 23, two_to_128.rem_u64(23));
+        assert_eq!(None, two_to_128.div_rem(&BigUint::default()));
+    }
+}
@@ -0,0 +1,111 @@
+use std::fmt;
+
+use crate::big_uint::BigUint;
+
+/// Decides to which monkey an item is thrown, from the `Test: ...` line.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TestCondition {
+    DivisibleBy(u64),
+    GreaterThan(u64),
+    LessThan(u64),
+    Even,
+    Odd,
+}
+
+impl TestCondition {
+    /// Parses the text after `Test: `, such as `divisible by 23`, `greater than 100`, `less than
+    /// 7`, `even` or `odd`.
+    pub fn parse(text: &str) -> Option<TestCondition> {
+        let words: Vec<&str> = text.split_whitespace().collect();
+        match words.as_slice() {
+            ["divisible", "by", divisor] => {
+                let divisor = divisor.parse().ok()?;
+                (divisor != 0).then_some(TestCondition::DivisibleBy(divisor))
+            }
+            ["greater", "than", bound] => bound.parse().ok().map(TestCondition::GreaterThan),
+            ["less", "than", bound] => bound.parse().ok().map(TestCondition::LessThan),
+            ["even"] => Some(TestCondition::Even),
+            ["odd"] => Some(TestCondition::Odd),
+            _ => None,
+        }
+    }
+
+    pub fn test(&self, worry_level: u64) -> bool {
+        match self {
+            TestCondition::DivisibleBy(divisor) => worry_level.is_multiple_of(*divisor),
+            TestCondition::GreaterThan(bound) => worry_level > *bound,
+            TestCondition::LessThan(bound) => worry_level < *bound,
+            TestCondition::Even => worry_level.is_multiple_of(2),
+            TestCondition::Odd => !worry_level.is_multiple_of(2),
+        }
+    }
+
+    pub fn test_big(&self, worry_level: &BigUint) -> bool {
+        match self {
+            TestCondition::DivisibleBy(divisor) => worry_level.rem_u64(*divisor) == 0,
+            TestCondition::GreaterThan(bound) => *worry_level > BigUint::from(*bound),
+            TestCondition::LessThan(bound) => *worry_level < BigUint::from(*bound),
+            TestCondition::Even => worry_level.rem_u64(2) == 0,
+            TestCondition::Odd => worry_level.rem_u64(2) == 1,
+        }
+    }
+
+    /// A modulus that worry levels can be reduced by without changing the outcome of the test,
+    /// if there is one. Comparisons depend on the actual magnitude, so they have none.
+    pub fn modulus(&self) -> Option<u64> {
+        match self {
+            TestCondition::DivisibleBy(divisor) => Some(*divisor),
+            TestCondition::Even | TestCondition::Odd => Some(2),
+            TestCondition::GreaterThan(_) | TestCondition::LessThan(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for TestCondition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TestCondition::DivisibleBy(divisor) => write!(f, "divisible by {}", divisor),
+            TestCondition::GreaterThan(bound) => write!(f, "greater than {}", bound),
+            TestCondition::LessThan(bound) => write!(f, "less than {}", bound),
+            TestCondition::Even => write!(f, "even"),
+            TestCondition::Odd => write!(f, "odd"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_test_conditions() {
+        let cases = [
+            ("divisible by 23", TestCondition::DivisibleBy(23), 46, 47),
+            (
+                "greater than 100",
+                TestCondition::GreaterThan(100),
+                101,
+                100,
+            ),
+            ("less than 7", TestCondition::LessThan(7), 6, 7),
+            ("even", TestCondition::Even, 4, 5),
+            ("odd", TestCondition::Odd, 5, 4),
+        ];
+
+        for (text, condition, passing, failing) in cases {
+            assert_eq!(Some(condition), TestCondition::parse(text));
+            assert_eq!(text, condition.to_string());
+            assert!(condition.test(passing), "{} {}", text, passing);
+            assert!(!condition.test(failing), "{} {}", text, failing);
+            assert!(condition.test_big(&BigUint::from(passing)));
+            assert!(!condition.test_big(&BigUint::from(failing)));
+        }
+    }
+
+    #[test]
+    fn test_invalid_conditions() {
+        assert_eq!(None, TestCondition::parse("divisible by 0"));
+        assert_eq!(None, TestCondition::parse("divisible by x"));
+        assert_eq!(None, TestCondition::parse("prime"));
+    }
+}
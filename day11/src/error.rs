@@ -0,0 +1,50 @@
+use std::fmt;
+
+use crate::condition::TestCondition;
+use crate::expression::{EvalError, Expression};
+
+/// Why a game cannot be set up or played on.
+#[derive(Debug, PartialEq)]
+pub enum GameError {
+    /// Modular relief changes the outcome of tests that have no modulus, such as comparisons.
+    TestWithoutModulus { monkey: u64, test: TestCondition },
+    /// Modular relief changes the outcome of operations that do more than add and multiply.
+    OperationWithoutModulus { monkey: u64, operation: Expression },
+    /// Only modular relief keeps the number of item states finite, which fast-forwarding
+    /// relies on.
+    FastForwardWithoutModularRelief,
+    /// The monkey's operation has no valid result for the item it inspected.
+    Evaluation {
+        round: u64,
+        monkey: u64,
+        error: EvalError,
+    },
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameError::TestWithoutModulus { monkey, test } => write!(
+                f,
+                "monkey {}: test '{}' cannot be combined with modular relief",
+                monkey, test
+            ),
+            GameError::OperationWithoutModulus { monkey, operation } => write!(
+                f,
+                "monkey {}: operation '{}' cannot be combined with modular relief, which only \
+                 allows '+' and '*'",
+                monkey, operation
+            ),
+            GameError::FastForwardWithoutModularRelief => {
+                write!(f, "only games with modular relief can be fast-forwarded")
+            }
+            GameError::Evaluation {
+                round,
+                monkey,
+                error,
+            } => write!(f, "round {}, monkey {}: {}", round, monkey, error),
+        }
+    }
+}
+
+impl std::error::Error for GameError {}
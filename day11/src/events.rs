@@ -0,0 +1,364 @@
+use std::fmt;
+use std::io::{self, Write};
+
+use crate::MonkeyGame;
+
+/// Everything that happens during a game, with rounds counted from 1 since the game started.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GameEvent {
+    RoundStarted {
+        round: u64,
+    },
+    TurnStarted {
+        round: u64,
+        monkey: u64,
+    },
+    /// The worry level before the inspection, after the operation and after relief.
+    Inspected {
+        round: u64,
+        monkey: u64,
+        worry_level: u64,
+        after_operation: u64,
+        after_relief: u64,
+    },
+    Thrown {
+        round: u64,
+        from: u64,
+        to: u64,
+        worry_level: u64,
+    },
+    Caught {
+        round: u64,
+        monkey: u64,
+        worry_level: u64,
+    },
+    TurnFinished {
+        round: u64,
+        monkey: u64,
+        inspection_count: u64,
+    },
+    /// The inspection count of every monkey so far, by monkey ID.
+    RoundFinished {
+        round: u64,
+        inspection_counts: Vec<(u64, u64)>,
+    },
+}
+
+/// Gets notified by [`MonkeyGame::play_observed`] about every event in order.
+pub trait GameObserver {
+    fn notify(&mut self, event: &GameEvent);
+}
+
+/// Prints every event in a readable form.
+pub struct GameLog;
+
+impl GameObserver for GameLog {
+    fn notify(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::RoundStarted { round } => println!("\nPlaying round {:02}", round),
+            GameEvent::TurnStarted { monkey, .. } => {
+                println!("\nIt's the turn of monkey {}", monkey)
+            }
+            GameEvent::Inspected {
+                monkey,
+                worry_level,
+                after_operation,
+                after_relief,
+                ..
+            } => {
+                println!("Monkey {} inspects item {}.", monkey, worry_level);
+                println!("New worry level after inspection: {}", after_operation);
+                println!("New worry level after relief: {}", after_relief);
+            }
+            GameEvent::Thrown {
+                from,
+                to,
+                worry_level,
+                ..
+            } => println!(
+                "Monkey {} throws item {} to monkey {}.",
+                from, worry_level, to
+            ),
+            GameEvent::Caught { .. } => {}
+            GameEvent::TurnFinished {
+                monkey,
+                inspection_count,
+                ..
+            } => {
+                println!("Turn of monkey {} finished.", monkey);
+                println!("Inspection count: {}", inspection_count);
+            }
+            GameEvent::RoundFinished {
+                round,
+                inspection_counts,
+            } => {
+                println!("\nRound {} finished. Inspection counts:", round);
+                for (monkey, inspection_count) in inspection_counts {
+                    println!(
+                        "Monkey {} inspected items {} times.",
+                        monkey, inspection_count
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Collects all inspections, throws, catches and per-round inspection counts.
+#[derive(Default)]
+pub struct EventLog {
+    pub events: Vec<GameEvent>,
+}
+
+impl GameObserver for EventLog {
+    fn notify(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::RoundStarted { .. }
+            | GameEvent::TurnStarted { .. }
+            | GameEvent::TurnFinished { .. } => {}
+            _ => self.events.push(event.clone()),
+        }
+    }
+}
+
+impl EventLog {
+    /// One row per event and one `inspections` row per monkey at the end of every round, with
+    /// empty cells for the columns that do not apply.
+    pub fn write_csv(&self, mut out: impl Write) -> io::Result<()> {
+        writeln!(
+            out,
+            "round,event,monkey,target,worry_level,inspection_count"
+        )?;
+        for event in &self.events {
+            match event {
+                GameEvent::Inspected {
+                    round,
+                    monkey,
+                    after_relief,
+                    ..
+                } => writeln!(out, "{},inspect,{},,{},", round, monkey, after_relief)?,
+                GameEvent::Thrown {
+                    round,
+                    from,
+                    to,
+                    worry_level,
+                } => writeln!(out, "{},throw,{},{},{},", round, from, to, worry_level)?,
+                GameEvent::Caught {
+                    round,
+                    monkey,
+                    worry_level,
+                } => writeln!(out, "{},catch,{},,{},", round, monkey, worry_level)?,
+                GameEvent::RoundFinished {
+                    round,
+                    inspection_counts,
+                } => {
+                    for (monkey, inspection_count) in inspection_counts {
+                        writeln!(
+                            out,
+                            "{},inspections,{},,,{}",
+                            round, monkey, inspection_count
+                        )?;
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// How often every monkey throws to every other monkey.
+pub struct ThrowMatrix {
+    /// The monkey IDs in turn order, the order of the rows and columns.
+    ids: Vec<u64>,
+    counts: Vec<Vec<u64>>,
+}
+
+impl ThrowMatrix {
+    pub fn new(game: &MonkeyGame) -> ThrowMatrix {
+        let ids: Vec<u64> = game.monkeys.iter().map(|monkey| monkey.id).collect();
+        let counts = vec![vec![0; ids.len()]; ids.len()];
+        ThrowMatrix { ids, counts }
+    }
+
+    fn index_of(&self, id: u64) -> usize {
+        self.ids
+            .iter()
+            .position(|&other| other == id)
+            .unwrap_or_else(|| panic!("Monkey {} is not in the game", id))
+    }
+
+    pub fn count(&self, from: u64, to: u64) -> u64 {
+        self.counts[self.index_of(from)][self.index_of(to)]
+    }
+
+    /// Every item a monkey inspects is thrown once, so this is its inspection count.
+    pub fn thrown_by(&self, monkey: u64) -> u64 {
+        self.counts[self.index_of(monkey)].iter().sum()
+    }
+
+    /// The throw graph with an edge from every monkey to every monkey it threw to, labelled
+    /// with the number of throws and drawn thicker the more items passed along it.
+    pub fn write_dot(&self, mut out: impl Write) -> io::Result<()> {
+        let max_count = self.counts.iter().flatten().copied().max().unwrap_or(0);
+
+        writeln!(out, "digraph throws {{")?;
+        for &id in &self.ids {
+            writeln!(
+                out,
+                "  {} [label=\"Monkey {}\\n{} inspections\"];",
+                id,
+                id,
+                self.thrown_by(id)
+            )?;
+        }
+        for &from in &self.ids {
+            for &to in &self.ids {
+                let count = self.count(from, to);
+                if count == 0 {
+                    continue;
+                }
+                let pen_width = 1.0 + 4.0 * count as f64 / max_count as f64;
+                writeln!(
+                    out,
+                    "  {} -> {} [label=\"{}\", penwidth={:.2}];",
+                    from, to, count, pen_width
+                )?;
+            }
+        }
+        writeln!(out, "}}")
+    }
+}
+
+impl GameObserver for ThrowMatrix {
+    fn notify(&mut self, event: &GameEvent) {
+        if let GameEvent::Thrown { from, to, .. } = event {
+            let (from, to) = (self.index_of(*from), self.index_of(*to));
+            self.counts[from][to] += 1;
+        }
+    }
+}
+
+impl fmt::Display for ThrowMatrix {
+    /// A table with a row per thrower and a column per catcher.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let width = self
+            .counts
+            .iter()
+            .flatten()
+            .map(|count| count.to_string().len())
+            .chain(self.ids.iter().map(|id| id.to_string().len()))
+            .max()
+            .unwrap_or(1);
+
+        write!(f, "{:>width$}", "", width = width)?;
+        for id in &self.ids {
+            write!(f, " {:>width$}", id, width = width)?;
+        }
+        for (id, row) in self.ids.iter().zip(&self.counts) {
+            write!(f, "\n{:>width$}", id, width = width)?;
+            for count in row {
+                write!(f, " {:>width$}", count, width = width)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_monkeys_from_input;
+    use crate::relief::DivideByThree;
+
+    fn play_test_game(rounds: u64, observers: &mut [&mut dyn GameObserver]) -> MonkeyGame {
+        let monkeys = parse_monkeys_from_input(include_str!("../test.txt"));
+        let mut game = MonkeyGame::new(monkeys, Box::new(DivideByThree)).unwrap();
+        game.play_observed(rounds, observers).unwrap();
+        game
+    }
+
+    #[test]
+    fn test_event_log() {
+        let mut log = EventLog::default();
+        play_test_game(1, &mut [&mut log]);
+
+        // Monkey 0 inspects 79, which becomes 79 * 19 = 1501 and 500 after relief.
+        assert_eq!(
+            GameEvent::Inspected {
+                round: 1,
+                monkey: 0,
+                worry_level: 79,
+                after_operation: 1501,
+                after_relief: 500
+            },
+            log.events[0]
+        );
+        assert_eq!(
+            GameEvent::Thrown {
+                round: 1,
+                from: 0,
+                to: 3,
+                worry_level: 500
+            },
+            log.events[1]
+        );
+        assert_eq!(
+            GameEvent::RoundFinished {
+                round: 1,
+                inspection_counts: vec![(0, 2), (1, 4), (2, 3), (3, 5)]
+            },
+            *log.events.last().unwrap()
+        );
+
+        let mut csv = Vec::new();
+        log.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            vec![
+                "round,event,monkey,target,worry_level,inspection_count",
+                "1,inspect,0,,500,",
+                "1,throw,0,3,500,",
+                "1,catch,3,,500,",
+            ],
+            lines[..4]
+        );
+        assert_eq!("1,inspections,3,,,5", *lines.last().unwrap());
+        // 14 inspections with a throw and a catch each, and a count for each of the 4 monkeys.
+        assert_eq!(1 + 14 * 3 + 4, lines.len());
+    }
+
+    #[test]
+    fn test_throw_matrix() {
+        let mut matrix = ThrowMatrix::new(&play_test_game(0, &mut []));
+        let game = play_test_game(20, &mut [&mut matrix]);
+
+        for monkey in &game.monkeys {
+            assert_eq!(monkey.inspection_count, matrix.thrown_by(monkey.id));
+        }
+        // Monkey 0 only throws to monkeys 2 and 3.
+        assert_eq!(0, matrix.count(0, 1));
+        assert_eq!(matrix.thrown_by(0), matrix.count(0, 2) + matrix.count(0, 3));
+        assert_eq!(
+            matrix
+                .to_string()
+                .lines()
+                .next()
+                .unwrap()
+                .split_whitespace()
+                .collect::<Vec<_>>(),
+            vec!["0", "1", "2", "3"]
+        );
+
+        let mut dot = Vec::new();
+        matrix.write_dot(&mut dot).unwrap();
+        let dot = String::from_utf8(dot).unwrap();
+        assert!(dot.starts_with("digraph throws {\n  0 [label=\"Monkey 0\\n101 inspections\"];\n"));
+        assert!(dot.contains(&format!(
+            "  0 -> 3 [label=\"{}\", penwidth=",
+            matrix.count(0, 3)
+        )));
+        assert!(!dot.contains("0 -> 1 "));
+    }
+}
@@ -0,0 +1,152 @@
+use std::collections::VecDeque;
+
+use crate::big_uint::BigUint;
+use crate::error::GameError;
+use crate::MonkeyGame;
+
+/// Continues a game without any relief, keeping every worry level exactly.
+///
+/// The worry levels grow without bounds. Every squaring doubles their number of digits, so
+/// depending on how often items are squared, only a limited number of rounds is feasible. That
+/// is enough to check relief policies against the ground truth.
+pub struct ExactGame<'a> {
+    /// Provides the monkeys' rules, its own items and inspection counts stay untouched.
+    game: &'a MonkeyGame,
+    items: Vec<VecDeque<BigUint>>,
+    inspection_counts: Vec<u64>,
+    rounds_played: u64,
+}
+
+impl<'a> ExactGame<'a> {
+    /// Starts with the items and inspection counts the game currently has.
+    pub fn new(game: &'a MonkeyGame) -> ExactGame<'a> {
+        let items = game
+            .monkeys
+            .iter()
+            .map(|monkey| {
+                monkey
+                    .items
+                    .iter()
+                    .map(|item| BigUint::from(item.worry_level))
+                    .collect()
+            })
+            .collect();
+        let inspection_counts = game
+            .monkeys
+            .iter()
+            .map(|monkey| monkey.inspection_count)
+            .collect();
+
+        ExactGame {
+            game,
+            items,
+            inspection_counts,
+            rounds_played: game.rounds_played,
+        }
+    }
+
+    /// Plays `rounds` rounds and returns the product of the two highest inspection counts. Like
+    /// [`MonkeyGame::play`], stops at the first inspection that fails.
+    pub fn play(&mut self, rounds: u64) -> Result<u64, GameError> {
+        for _ in 0..rounds {
+            self.rounds_played += 1;
+            for (monkey_idx, monkey) in self.game.monkeys.iter().enumerate() {
+                let (target_true, target_false) = self.game.throw_targets[monkey_idx];
+
+                while let Some(worry_level) = self.items[monkey_idx].pop_front() {
+                    self.inspection_counts[monkey_idx] += 1;
+                    let worry_level =
+                        monkey
+                            .worry_operation
+                            .evaluate_big(&worry_level)
+                            .map_err(|error| GameError::Evaluation {
+                                round: self.rounds_played,
+                                monkey: monkey.id,
+                                error,
+                            })?;
+
+                    let target = if monkey.test.test_big(&worry_level) {
+                        target_true
+                    } else {
+                        target_false
+                    };
+                    self.items[target].push_back(worry_level);
+                }
+            }
+        }
+
+        let mut inspection_counts = self.inspection_counts.clone();
+        inspection_counts.sort_unstable_by_key(|&count| std::cmp::Reverse(count));
+        Ok(inspection_counts.iter().take(2).product())
+    }
+
+    /// In turn order, like the game's monkeys.
+    pub fn inspection_counts(&self) -> &[u64] {
+        &self.inspection_counts
+    }
+
+    /// The worry levels of the items the monkey at `monkey_idx` in turn order holds.
+    pub fn worry_levels(&self, monkey_idx: usize) -> &VecDeque<BigUint> {
+        &self.items[monkey_idx]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_monkeys_from_input;
+    use crate::relief::{DivideThenModulo, ModuloLcm, NoRelief, ReliefPolicy};
+
+    fn game(relief: Box<dyn ReliefPolicy>) -> MonkeyGame {
+        MonkeyGame::new(
+            parse_monkeys_from_input(include_str!("../test.txt")),
+            relief,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_modulo_lcm_matches_exact_worry_levels() {
+        let mut modular = game(Box::new(ModuloLcm));
+        let worry_modulus = modular.worry_modulus;
+        let exact_game = game(Box::new(NoRelief));
+        let mut exact = ExactGame::new(&exact_game);
+
+        for _ in 0..100 {
+            assert_eq!(modular.play_observed(1, &mut []), exact.play(1));
+
+            let inspection_counts: Vec<u64> = modular
+                .monkeys
+                .iter()
+                .map(|monkey| monkey.inspection_count)
+                .collect();
+            assert_eq!(inspection_counts, exact.inspection_counts());
+            for (idx, monkey) in modular.monkeys.iter().enumerate() {
+                let reduced: Vec<u64> = exact
+                    .worry_levels(idx)
+                    .iter()
+                    .map(|worry_level| worry_level.rem_u64(worry_modulus))
+                    .collect();
+                let worry_levels: Vec<u64> =
+                    monkey.items.iter().map(|item| item.worry_level).collect();
+                assert_eq!(worry_levels, reduced);
+            }
+        }
+
+        // Far beyond what fits into a u64, which the game without relief would overflow on.
+        let max_bits = (0..4)
+            .flat_map(|idx| exact.worry_levels(idx).iter().map(BigUint::bit_len))
+            .max()
+            .unwrap();
+        assert!(max_bits > 1000, "{}", max_bits);
+    }
+
+    #[test]
+    fn test_dividing_before_the_modulo_breaks_the_congruences() {
+        let mut divided = game(Box::new(DivideThenModulo(2)));
+        let exact_game = game(Box::new(NoRelief));
+        let mut exact = ExactGame::new(&exact_game);
+
+        assert_ne!(divided.play_observed(10, &mut []), exact.play(10));
+    }
+}
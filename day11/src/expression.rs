@@ -0,0 +1,432 @@
+use std::fmt;
+
+use crate::big_uint::BigUint;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+}
+
+impl Operator {
+    fn symbol(self) -> char {
+        match self {
+            Operator::Add => '+',
+            Operator::Subtract => '-',
+            Operator::Multiply => '*',
+            Operator::Divide => '/',
+            Operator::Remainder => '%',
+        }
+    }
+
+    /// Operators with a higher precedence bind more tightly.
+    fn precedence(self) -> u8 {
+        match self {
+            Operator::Add | Operator::Subtract => 1,
+            Operator::Multiply | Operator::Divide | Operator::Remainder => 2,
+        }
+    }
+
+    fn apply(self, lhs: u64, rhs: u64) -> Result<u64, EvalError> {
+        let result = match self {
+            Operator::Add => lhs.checked_add(rhs),
+            Operator::Subtract => lhs.checked_sub(rhs),
+            Operator::Multiply => lhs.checked_mul(rhs),
+            Operator::Divide => lhs.checked_div(rhs),
+            Operator::Remainder => lhs.checked_rem(rhs),
+        };
+        result.ok_or_else(|| EvalError {
+            operator: self,
+            lhs: BigUint::from(lhs),
+            rhs: BigUint::from(rhs),
+        })
+    }
+
+    fn apply_big(self, lhs: &BigUint, rhs: &BigUint) -> Result<BigUint, EvalError> {
+        let result = match self {
+            Operator::Add => Some(lhs + rhs),
+            Operator::Subtract => lhs.checked_sub(rhs),
+            Operator::Multiply => Some(lhs * rhs),
+            Operator::Divide => lhs.div_rem(rhs).map(|(quotient, _)| quotient),
+            Operator::Remainder => lhs.div_rem(rhs).map(|(_, remainder)| remainder),
+        };
+        result.ok_or_else(|| EvalError {
+            operator: self,
+            lhs: lhs.clone(),
+            rhs: rhs.clone(),
+        })
+    }
+}
+
+/// The right hand side of an `Operation: new = ...` line, compiled once and evaluated for every
+/// inspection.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expression {
+    Old,
+    Literal(u64),
+    Binary(Box<Expression>, Operator, Box<Expression>),
+}
+
+impl Expression {
+    /// Parses integer literals and `old` combined with `+ - * / %` and parentheses. `*`, `/`
+    /// and `%` bind more tightly than `+` and `-`, and operators of the same precedence are
+    /// evaluated from left to right.
+    pub fn parse(source: &str) -> Result<Expression, ExpressionError> {
+        let mut parser = Parser {
+            tokens: tokenize(source)?,
+            position: 0,
+            end_column: source.len() + 1,
+        };
+        let expression = parser.parse_expression(0)?;
+        match parser.tokens.get(parser.position) {
+            None => Ok(expression),
+            Some((column, _)) => Err(ExpressionError {
+                column: *column,
+                message: String::from("expected an operator"),
+            }),
+        }
+    }
+
+    /// Whether the result modulo any number only depends on `old` modulo that number, which
+    /// holds as long as the expression only adds and multiplies.
+    pub fn preserves_congruences(&self) -> bool {
+        match self {
+            Expression::Old | Expression::Literal(_) => true,
+            Expression::Binary(lhs, operator, rhs) => {
+                matches!(operator, Operator::Add | Operator::Multiply)
+                    && lhs.preserves_congruences()
+                    && rhs.preserves_congruences()
+            }
+        }
+    }
+
+    /// Fails if the worry level would become negative or overflow, or on a division by zero.
+    pub fn evaluate(&self, old: u64) -> Result<u64, EvalError> {
+        match self {
+            Expression::Old => Ok(old),
+            Expression::Literal(value) => Ok(*value),
+            Expression::Binary(lhs, operator, rhs) => {
+                operator.apply(lhs.evaluate(old)?, rhs.evaluate(old)?)
+            }
+        }
+    }
+
+    /// Like [`Expression::evaluate`], but can only fail on negative worry levels or a division
+    /// by zero.
+    pub fn evaluate_big(&self, old: &BigUint) -> Result<BigUint, EvalError> {
+        match self {
+            Expression::Old => Ok(old.clone()),
+            Expression::Literal(value) => Ok(BigUint::from(*value)),
+            Expression::Binary(lhs, operator, rhs) => {
+                operator.apply_big(&lhs.evaluate_big(old)?, &rhs.evaluate_big(old)?)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Expression {
+    /// Fully parenthesized, so that the result parses to the same expression.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expression::Old => write!(f, "old"),
+            Expression::Literal(value) => write!(f, "{}", value),
+            Expression::Binary(lhs, operator, rhs) => {
+                write!(f, "({} {} {})", lhs, operator.symbol(), rhs)
+            }
+        }
+    }
+}
+
+/// Where an expression is invalid, `column` counting characters from 1.
+#[derive(Debug, PartialEq)]
+pub struct ExpressionError {
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ExpressionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "column {}: {}", self.column, self.message)
+    }
+}
+
+impl std::error::Error for ExpressionError {}
+
+/// An operation that has no valid worry level as its result.
+#[derive(Debug, PartialEq)]
+pub struct EvalError {
+    pub operator: Operator,
+    pub lhs: BigUint,
+    pub rhs: BigUint,
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let reason = match self.operator {
+            Operator::Divide | Operator::Remainder if self.rhs.is_zero() => "division by zero",
+            _ => "worry level out of range",
+        };
+        write!(
+            f,
+            "{} evaluating {} {} {}",
+            reason,
+            self.lhs,
+            self.operator.symbol(),
+            self.rhs
+        )
+    }
+}
+
+impl std::error::Error for EvalError {}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Old,
+    Literal(u64),
+    Operator(Operator),
+    OpenParen,
+    CloseParen,
+}
+
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, ExpressionError> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+
+    while let Some((idx, c)) = chars.next() {
+        let column = idx + 1;
+        let token = match c {
+            ' ' | '\t' => continue,
+            '+' => Token::Operator(Operator::Add),
+            '-' => Token::Operator(Operator::Subtract),
+            '*' => Token::Operator(Operator::Multiply),
+            '/' => Token::Operator(Operator::Divide),
+            '%' => Token::Operator(Operator::Remainder),
+            '(' => Token::OpenParen,
+            ')' => Token::CloseParen,
+            _ if c.is_ascii_alphanumeric() => {
+                let mut end = idx + c.len_utf8();
+                while let Some(&(next_idx, next)) = chars.peek() {
+                    if !next.is_ascii_alphanumeric() {
+                        break;
+                    }
+                    end = next_idx + next.len_utf8();
+                    chars.next();
+                }
+                let word = &source[idx..end];
+                if word == "old" {
+                    Token::Old
+                } else {
+                    Token::Literal(word.parse().map_err(|_| ExpressionError {
+                        column,
+                        message: format!("invalid number '{}'", word),
+                    })?)
+                }
+            }
+            _ => {
+                return Err(ExpressionError {
+                    column,
+                    message: format!("unexpected character '{}'", c),
+                })
+            }
+        };
+        tokens.push((column, token));
+    }
+
+    Ok(tokens)
+}
+
+/// A precedence climbing parser over the tokens.
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    position: usize,
+    end_column: usize,
+}
+
+impl Parser {
+    fn parse_expression(&mut self, min_precedence: u8) -> Result<Expression, ExpressionError> {
+        let mut lhs = self.parse_operand()?;
+
+        while let Some((_, Token::Operator(operator))) = self.tokens.get(self.position) {
+            let operator = *operator;
+            if operator.precedence() <= min_precedence {
+                break;
+            }
+            self.position += 1;
+            let rhs = self.parse_expression(operator.precedence())?;
+            lhs = Expression::Binary(Box::new(lhs), operator, Box::new(rhs));
+        }
+
+        Ok(lhs)
+    }
+
+    fn parse_operand(&mut self) -> Result<Expression, ExpressionError> {
+        let Some((column, token)) = self.tokens.get(self.position).cloned() else {
+            return Err(ExpressionError {
+                column: self.end_column,
+                message: String::from("unexpected end of expression"),
+            });
+        };
+        self.position += 1;
+
+        match token {
+            Token::Old => Ok(Expression::Old),
+            Token::Literal(value) => Ok(Expression::Literal(value)),
+            Token::OpenParen => {
+                let expression = self.parse_expression(0)?;
+                match self.tokens.get(self.position) {
+                    Some((_, Token::CloseParen)) => {
+                        self.position += 1;
+                        Ok(expression)
+                    }
+                    Some((column, _)) => Err(ExpressionError {
+                        column: *column,
+                        message: String::from("expected ')'"),
+                    }),
+                    None => Err(ExpressionError {
+                        column: self.end_column,
+                        message: String::from("missing ')'"),
+                    }),
+                }
+            }
+            Token::Operator(_) | Token::CloseParen => Err(ExpressionError {
+                column,
+                message: String::from("expected a number, 'old' or '('"),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_evaluate() {
+        let cases = [
+            ("old * 19", 10, 190),
+            ("old * old", 7, 49),
+            ("old + 3", 5, 8),
+            ("(old * 3 + 7) * old", 2, 26),
+            ("old * 3 + 7 * old", 2, 20),
+            ("old - 4 - 3", 10, 3),
+            ("100 / old / 2", 5, 10),
+            ("old % 7 * 2", 10, 6),
+            ("((old))", 42, 42),
+        ];
+
+        for (source, old, want) in cases {
+            let expression = Expression::parse(source).unwrap();
+            assert_eq!(Ok(want), expression.evaluate(old), "{}", source);
+            assert_eq!(
+                expression,
+                Expression::parse(&expression.to_string()).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_evaluate_big() {
+        let expression =
+            Expression::parse("(old * old + 7) % 1000000007 + old / 3 - old % 2").unwrap();
+
+        for old in [0, 5, 1_000_000, u32::MAX as u64] {
+            assert_eq!(
+                expression.evaluate(old).unwrap(),
+                expression
+                    .evaluate_big(&BigUint::from(old))
+                    .unwrap()
+                    .to_u64()
+                    .unwrap(),
+                "{}",
+                old
+            );
+        }
+        assert_eq!(
+            "18446744073709551616",
+            Expression::parse("old * old")
+                .unwrap()
+                .evaluate_big(&BigUint::from(1 << 32))
+                .unwrap()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn test_preserves_congruences() {
+        let preserves = |source| Expression::parse(source).unwrap().preserves_congruences();
+
+        assert!(preserves("old * 19"));
+        assert!(preserves("(old * 3 + 7) * old"));
+        assert!(!preserves("old - 4"));
+        assert!(!preserves("(old + 1) / 2"));
+        assert!(!preserves("old * (old % 7)"));
+    }
+
+    #[test]
+    fn test_precedence_and_associativity() {
+        assert_eq!(
+            "((old - 4) - (3 * old))",
+            Expression::parse("old - 4 - 3 * old").unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |source| Expression::parse(source).unwrap_err();
+
+        assert_eq!(5, error("old ^ 2").column);
+        assert_eq!(
+            "column 7: invalid number '2x'",
+            error("old * 2x").to_string()
+        );
+        assert_eq!(6, error("old +").column);
+        assert_eq!(10, error("(old + 1 old").column);
+        assert_eq!(9, error("(old + 1").column);
+        assert_eq!(1, error("* old").column);
+        assert_eq!(5, error("old old").column);
+    }
+
+    #[test]
+    fn test_evaluation_errors() {
+        let error = |source, old| {
+            Expression::parse(source)
+                .unwrap()
+                .evaluate(old)
+                .unwrap_err()
+        };
+
+        assert_eq!(
+            EvalError {
+                operator: Operator::Subtract,
+                lhs: BigUint::from(3),
+                rhs: BigUint::from(5),
+            },
+            error("old - 5", 3)
+        );
+        assert_eq!(
+            "worry level out of range evaluating 3 - 5",
+            error("old - 5", 3).to_string()
+        );
+        assert_eq!(
+            "division by zero evaluating 10 / 0",
+            error("10 / (old - 4)", 4).to_string()
+        );
+        assert_eq!(
+            "division by zero evaluating 4 % 0",
+            error("old % 0", 4).to_string()
+        );
+        assert_eq!(
+            "worry level out of range evaluating 4294967296 * 4294967296",
+            error("old * old", 1 << 32).to_string()
+        );
+
+        let big_error = Expression::parse("old - 5")
+            .unwrap()
+            .evaluate_big(&BigUint::from(3))
+            .unwrap_err();
+        assert_eq!(error("old - 5", 3), big_error);
+    }
+}
@@ -0,0 +1,261 @@
+use std::collections::HashMap;
+
+use crate::error::GameError;
+use crate::{Item, MonkeyGame};
+
+/// Which monkey holds an item at the start of a round, and the item's worry level.
+type ItemState = (usize, u64);
+
+impl MonkeyGame {
+    /// Plays `rounds` rounds silently and returns the monkey business like [`MonkeyGame::play`],
+    /// without simulating every round.
+    ///
+    /// Items never interact, so every item is followed on its own. Its state at the start of a
+    /// round determines everything it does during the round, so once a state repeats, the item
+    /// is in a cycle and the remaining rounds are extrapolated. Relief policies that keep worry
+    /// levels modulo the LCM bound the number of states, so every item ends up in a cycle after
+    /// at most `monkeys * worry_modulus` rounds. Items still end up with the right monkeys, but
+    /// possibly in a different order than after playing.
+    ///
+    /// Fails without modular relief, where states would pile up for every round. Also fails if
+    /// any inspection fails, leaving the game untouched.
+    pub fn fast_forward(&mut self, rounds: u64) -> Result<u64, GameError> {
+        if !self.relief.is_modular() {
+            return Err(GameError::FastForwardWithoutModularRelief);
+        }
+
+        let mut outcomes = Vec::new();
+        for (idx, monkey) in self.monkeys.iter().enumerate() {
+            for item in &monkey.items {
+                outcomes.push(self.fast_forward_item((idx, item.worry_level), rounds)?);
+            }
+        }
+
+        for monkey in &mut self.monkeys {
+            monkey.items.clear();
+        }
+        for (inspection_counts, (holder, worry_level)) in outcomes {
+            for (monkey, inspection_count) in self.monkeys.iter_mut().zip(inspection_counts) {
+                monkey.inspection_count += inspection_count;
+            }
+            self.monkeys[holder].catch_item(Item { worry_level });
+        }
+        self.rounds_played += rounds;
+
+        Ok(self.multiply_two_highest_inspection_counts())
+    }
+
+    /// How often every monkey inspects the item during `rounds` rounds, and its final state.
+    fn fast_forward_item(
+        &self,
+        start: ItemState,
+        rounds: u64,
+    ) -> Result<(Vec<u64>, ItemState), GameError> {
+        let mut first_seen: HashMap<ItemState, usize> = HashMap::new();
+        let mut states = Vec::new();
+        // The inspections per monkey during the first `round` rounds, for every round.
+        let mut cumulative_counts = vec![vec![0; self.monkeys.len()]];
+
+        let mut state = start;
+        loop {
+            let round = states.len();
+            if round as u64 == rounds {
+                return Ok((cumulative_counts.swap_remove(round), state));
+            }
+            if let Some(&cycle_start) = first_seen.get(&state) {
+                let cycle_length = round - cycle_start;
+                let remaining_rounds = rounds - round as u64;
+                let full_cycles = remaining_rounds / cycle_length as u64;
+                let partial_end = cycle_start + (remaining_rounds % cycle_length as u64) as usize;
+
+                let inspection_counts = (0..self.monkeys.len())
+                    .map(|idx| {
+                        let before_cycle = cumulative_counts[cycle_start][idx];
+                        let cycle = cumulative_counts[round][idx] - before_cycle;
+                        let partial = cumulative_counts[partial_end][idx] - before_cycle;
+                        cumulative_counts[round][idx] + full_cycles * cycle + partial
+                    })
+                    .collect();
+                return Ok((inspection_counts, states[partial_end]));
+            }
+
+            first_seen.insert(state, round);
+            states.push(state);
+            let mut inspection_counts = cumulative_counts[round].clone();
+            let round = self.rounds_played + round as u64 + 1;
+            state = self.play_item_round(state, round, &mut inspection_counts)?;
+            cumulative_counts.push(inspection_counts);
+        }
+    }
+
+    /// Follows the item through one round. Thrown to a monkey after its holder, the item is
+    /// inspected again in the same round, otherwise it waits for the next one.
+    fn play_item_round(
+        &self,
+        state: ItemState,
+        round: u64,
+        inspection_counts: &mut [u64],
+    ) -> Result<ItemState, GameError> {
+        let (mut holder, mut worry_level) = state;
+        loop {
+            let monkey = &self.monkeys[holder];
+            inspection_counts[holder] += 1;
+            worry_level = monkey
+                .worry_operation
+                .evaluate(worry_level)
+                .map_err(|error| GameError::Evaluation {
+                    round,
+                    monkey: monkey.id,
+                    error,
+                })?;
+            worry_level = self.relief.relieve(worry_level, self.worry_modulus);
+
+            let (target_true, target_false) = self.throw_targets[holder];
+            let target = if monkey.test.test(worry_level) {
+                target_true
+            } else {
+                target_false
+            };
+            if target < holder {
+                return Ok((target, worry_level));
+            }
+            holder = target;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_monkeys_from_input;
+    use crate::relief::{DivideByThree, DivideThenModulo, ModuloLcm, NoRelief, ReliefPolicy};
+
+    fn game(relief: Box<dyn ReliefPolicy>) -> MonkeyGame {
+        MonkeyGame::new(
+            parse_monkeys_from_input(include_str!("../test.txt")),
+            relief,
+        )
+        .unwrap()
+    }
+
+    /// The inspection counts and the sorted worry levels held by every monkey.
+    fn summary(game: &MonkeyGame) -> Vec<(u64, Vec<u64>)> {
+        game.monkeys
+            .iter()
+            .map(|monkey| {
+                let mut worry_levels: Vec<u64> =
+                    monkey.items.iter().map(|item| item.worry_level).collect();
+                worry_levels.sort_unstable();
+                (monkey.inspection_count, worry_levels)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_fast_forward_matches_playing() {
+        let input = include_str!("../input.txt");
+        for rounds in [0, 1, 2, 20, 150] {
+            for dividing in [false, true] {
+                let relief = || -> Box<dyn ReliefPolicy> {
+                    if dividing {
+                        Box::new(DivideThenModulo(3))
+                    } else {
+                        Box::new(ModuloLcm)
+                    }
+                };
+                let mut played =
+                    MonkeyGame::new(parse_monkeys_from_input(input), relief()).unwrap();
+                let mut fast = MonkeyGame::new(parse_monkeys_from_input(input), relief()).unwrap();
+
+                assert_eq!(played.play(rounds), fast.fast_forward(rounds));
+                assert_eq!(summary(&played), summary(&fast), "{} rounds", rounds);
+            }
+        }
+    }
+
+    #[test]
+    fn test_fast_forward_requires_modular_relief() {
+        for relief in [
+            Box::new(DivideByThree) as Box<dyn ReliefPolicy>,
+            Box::new(NoRelief),
+        ] {
+            let mut fast = game(relief);
+            assert_eq!(
+                Err(GameError::FastForwardWithoutModularRelief),
+                fast.fast_forward(10_000_000)
+            );
+            assert_eq!(0, fast.rounds_played);
+            assert_eq!(2, fast.monkeys[0].items.len());
+        }
+    }
+
+    #[test]
+    fn test_failing_fast_forward_leaves_the_game_untouched() {
+        // The first item stays at 1, squaring the second one overflows in the first round.
+        let input = "\
+Monkey 0:
+  Starting items: 1, 5000000000
+  Operation: new = old * old
+  Test: divisible by 4294967311
+    If true: throw to monkey 1
+    If false: throw to monkey 1
+
+Monkey 1:
+  Starting items:
+  Operation: new = old
+  Test: divisible by 2
+    If true: throw to monkey 0
+    If false: throw to monkey 0
+";
+        let mut fast =
+            MonkeyGame::new(parse_monkeys_from_input(input), Box::new(ModuloLcm)).unwrap();
+        let before = summary(&fast);
+
+        let err = fast.fast_forward(100).unwrap_err();
+        assert!(matches!(
+            err,
+            GameError::Evaluation {
+                round: 1,
+                monkey: 0,
+                ..
+            }
+        ));
+        assert_eq!(before, summary(&fast));
+        assert_eq!(0, fast.rounds_played);
+    }
+
+    #[test]
+    fn test_fast_forward_continues_a_game() {
+        let mut played = game(Box::new(ModuloLcm));
+        let mut fast = game(Box::new(ModuloLcm));
+
+        played.play(300).unwrap();
+        fast.fast_forward(100).unwrap();
+        fast.play(100).unwrap();
+
+        assert_eq!(played.play(0), fast.fast_forward(100));
+        assert_eq!(summary(&played), summary(&fast));
+    }
+
+    #[test]
+    fn test_fast_forward_part2() {
+        assert_eq!(
+            Ok(2713310158),
+            game(Box::new(ModuloLcm)).fast_forward(10000)
+        );
+    }
+
+    #[test]
+    fn test_fast_forward_ten_million_rounds() {
+        let mut ten_thousand = game(Box::new(ModuloLcm));
+        ten_thousand.fast_forward(10_000).unwrap();
+        let mut ten_million = game(Box::new(ModuloLcm));
+        ten_million.fast_forward(10_000_000).unwrap();
+
+        // Every inspection count grows roughly in proportion to the rounds.
+        for (few, many) in ten_thousand.monkeys.iter().zip(&ten_million.monkeys) {
+            let ratio = many.inspection_count as f64 / few.inspection_count as f64;
+            assert!((990.0..1010.0).contains(&ratio), "{}", ratio);
+        }
+    }
+}
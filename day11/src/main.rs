@@ -1,35 +1,24 @@
 use std::collections::VecDeque;
-
-#[derive(Debug)]
-enum WorryOperation {
-    AddConstant(u64),
-    MultiplyByConstant(u64),
-    Square,
-    SquarePart2,
-    MultiplyByConstantPart2(u64),
-    AddConstantPart2(u64),
-}
-
-impl WorryOperation {
-    fn calculate_new_worry_level(&self, old_value: u64, divisor: Option<u64>) -> u64 {
-        match self {
-            WorryOperation::AddConstant(c) => old_value + c,
-            WorryOperation::MultiplyByConstant(c) => old_value * c,
-            WorryOperation::Square => old_value * old_value,
-            WorryOperation::SquarePart2 => (old_value * old_value) % divisor.unwrap(),
-            WorryOperation::MultiplyByConstantPart2(c) => (old_value * c) % divisor.unwrap(),
-            WorryOperation::AddConstantPart2(c) => (old_value + c) % divisor.unwrap(),
-        }
-    }
-
-    fn is_divisable_by(&self, worry_level: u64, divisor: u64) -> bool {
-        if worry_level % divisor == 0 {
-            return true;
-        } else {
-            return false;
-        }
-    }
-}
+use std::fs::File;
+use std::io::BufWriter;
+
+mod big_uint;
+mod condition;
+mod error;
+mod events;
+mod exact;
+mod expression;
+mod fast_forward;
+mod parser;
+mod relief;
+
+use condition::TestCondition;
+use error::GameError;
+use events::{EventLog, GameEvent, GameLog, GameObserver, ThrowMatrix};
+use exact::ExactGame;
+use expression::{EvalError, Expression};
+use parser::parse_monkeys;
+use relief::{DivideByThree, DivideThenModulo, ModuloLcm, NoRelief, ReliefPolicy};
 
 struct ThrowingItem {
     item: Item,
@@ -39,17 +28,16 @@ struct ThrowingItem {
 #[derive(Debug)]
 struct Item {
     worry_level: u64,
-    divisor_product: Option<u64>,
 }
 
 impl Item {
-    fn inspect(&mut self, update_fn: &WorryOperation) {
-        self.worry_level =
-            update_fn.calculate_new_worry_level(self.worry_level, self.divisor_product);
+    fn inspect(&mut self, worry_operation: &Expression) -> Result<(), EvalError> {
+        self.worry_level = worry_operation.evaluate(self.worry_level)?;
+        Ok(())
     }
 
-    fn get_bored(&mut self) {
-        self.worry_level = self.worry_level / 3;
+    fn get_relieved(&mut self, relief: &dyn ReliefPolicy, worry_modulus: u64) {
+        self.worry_level = relief.relieve(self.worry_level, worry_modulus);
     }
 }
 
@@ -57,8 +45,8 @@ impl Item {
 struct Monkey {
     id: u64,
     items: VecDeque<Item>,
-    worry_operation: WorryOperation,
-    test_divisor: u64,
+    worry_operation: Expression,
+    test: TestCondition,
     throw_to_monkey_id_if_true: u64,
     throw_to_monkey_id_if_false: u64,
     inspection_count: u64,
@@ -68,20 +56,20 @@ impl Monkey {
     fn new(
         id: u64,
         initial_items: VecDeque<Item>,
-        worry_operation: WorryOperation,
-        test_divisor: u64,
+        worry_operation: Expression,
+        test: TestCondition,
         throw_to_monkey_id_if_true: u64,
         throw_to_monkey_id_if_false: u64,
     ) -> Monkey {
-        return Monkey {
+        Monkey {
             id,
             items: initial_items,
             worry_operation,
-            test_divisor,
+            test,
             throw_to_monkey_id_if_true,
             throw_to_monkey_id_if_false,
             inspection_count: 0,
-        };
+        }
     }
 
     fn catch_item(&mut self, item: Item) {
@@ -89,26 +77,23 @@ impl Monkey {
     }
 
     fn has_item(&self) -> bool {
-        if self.items.len() > 0 {
-            true
-        } else {
-            false
-        }
+        !self.items.is_empty()
     }
 
-    fn inspect_item(&mut self, is_part1: bool) {
+    /// Returns the worry level after the operation and after relief.
+    fn inspect_item(
+        &mut self,
+        relief: &dyn ReliefPolicy,
+        worry_modulus: u64,
+    ) -> Result<(u64, u64), EvalError> {
         let item = self.items.get_mut(0).unwrap();
-
-        println!("Monkey {} inspects item {}.", self.id, item.worry_level);
         self.inspection_count += 1;
 
-        item.inspect(&self.worry_operation);
-        println!("New worry level after inspection: {}", item.worry_level);
+        item.inspect(&self.worry_operation)?;
+        let after_operation = item.worry_level;
 
-        if is_part1 {
-            item.get_bored();
-            println!("New worry level after getting bored: {}", item.worry_level);
-        }
+        item.get_relieved(relief, worry_modulus);
+        Ok((after_operation, item.worry_level))
     }
 
     fn throw_next_item(&mut self) -> ThrowingItem {
@@ -117,273 +102,396 @@ impl Monkey {
 
         ThrowingItem {
             item,
-            test_result: self
-                .worry_operation
-                .is_divisable_by(worry_level, self.test_divisor),
+            test_result: self.test.test(worry_level),
         }
     }
 }
 
 struct MonkeyGame {
+    /// Sorted by ID, the order in which the monkeys take their turns.
     monkeys: Vec<Monkey>,
-    is_part1: bool,
+    /// The indices of the monkeys each monkey throws to if its test is true and if it is false.
+    throw_targets: Vec<(usize, usize)>,
+    relief: Box<dyn ReliefPolicy>,
+    worry_modulus: u64,
+    rounds_played: u64,
 }
 
 impl MonkeyGame {
-    fn new(monkeys: Vec<Monkey>, is_part1: bool) -> MonkeyGame {
-        return MonkeyGame { monkeys, is_part1 };
-    }
-
-    fn get_mutable_monkeys(
-        &mut self,
-        main_monkey_id: u64,
-        target_monkey_id_true: u64,
-        target_monkey_id_false: u64,
-    ) -> (
-        Option<&mut Monkey>,
-        Option<&mut Monkey>,
-        Option<&mut Monkey>,
-    ) {
-        let mut main_monkey: Option<&mut Monkey> = None;
-        let mut target_monkey_true: Option<&mut Monkey> = None;
-        let mut target_monkey_false: Option<&mut Monkey> = None;
-
-        let mut current_monkey: &mut [Monkey];
-        let length = self.monkeys.len() + 1;
-        let mut rest: &mut [Monkey] = &mut self.monkeys;
-
-        for _ in 1..length {
-            (current_monkey, rest) = rest.split_at_mut(1);
-            if current_monkey[0].id == main_monkey_id {
-                main_monkey = Some(&mut current_monkey[0]);
-            } else if current_monkey[0].id == target_monkey_id_true {
-                target_monkey_true = Some(&mut current_monkey[0]);
-            } else if current_monkey[0].id == target_monkey_id_false {
-                target_monkey_false = Some(&mut current_monkey[0]);
+    /// Fails if `relief` is modular but a monkey's test or operation does not keep working
+    /// modulo the LCM, see [`worry_modulus`].
+    ///
+    /// Panics if a monkey throws to a monkey that is not in the game, which
+    /// [`parse_monkeys`] rules out.
+    fn new(
+        mut monkeys: Vec<Monkey>,
+        relief: Box<dyn ReliefPolicy>,
+    ) -> Result<MonkeyGame, GameError> {
+        monkeys.sort_by_key(|monkey| monkey.id);
+        if relief.is_modular() {
+            for monkey in &monkeys {
+                if monkey.test.modulus().is_none() {
+                    return Err(GameError::TestWithoutModulus {
+                        monkey: monkey.id,
+                        test: monkey.test,
+                    });
+                }
+                if !monkey.worry_operation.preserves_congruences() {
+                    return Err(GameError::OperationWithoutModulus {
+                        monkey: monkey.id,
+                        operation: monkey.worry_operation.clone(),
+                    });
+                }
             }
         }
 
-        (main_monkey, target_monkey_true, target_monkey_false)
+        let index_of = |id| {
+            monkeys
+                .binary_search_by_key(&id, |monkey| monkey.id)
+                .unwrap_or_else(|_| panic!("There is no monkey {} to throw to", id))
+        };
+        let throw_targets = monkeys
+            .iter()
+            .map(|monkey| {
+                (
+                    index_of(monkey.throw_to_monkey_id_if_true),
+                    index_of(monkey.throw_to_monkey_id_if_false),
+                )
+            })
+            .collect();
+
+        let worry_modulus = worry_modulus(&monkeys);
+        Ok(MonkeyGame {
+            monkeys,
+            throw_targets,
+            relief,
+            worry_modulus,
+            rounds_played: 0,
+        })
     }
 
-    fn play(&mut self, rounds: u64) -> u64 {
-        let is_part1 = self.is_part1;
-
-        for idx in 0..rounds {
-            println!("\nPlaying round {}", format!("{:02}", idx + 1));
-
-            for monkey_id in 0..self.monkeys.len() {
-                println!("\nIt's the turn of monkey {}", monkey_id);
-
-                let monkey = &self.monkeys[monkey_id];
+    /// Plays `rounds` rounds silently and returns the monkey business.
+    fn play(&mut self, rounds: u64) -> Result<u64, GameError> {
+        self.play_observed(rounds, &mut [])
+    }
 
-                let mutable_monkeys = self.get_mutable_monkeys(
-                    monkey.id,
-                    monkey.throw_to_monkey_id_if_true,
-                    monkey.throw_to_monkey_id_if_false,
-                );
-                let main_monkey = mutable_monkeys.0.unwrap();
-                let target_monkey_true = mutable_monkeys.1.unwrap();
-                let target_monkey_false = mutable_monkeys.2.unwrap();
+    /// Stops at the first inspection that fails, leaving the game in the middle of the turn.
+    fn play_observed(
+        &mut self,
+        rounds: u64,
+        observers: &mut [&mut dyn GameObserver],
+    ) -> Result<u64, GameError> {
+        let mut notify = |event: GameEvent| {
+            for observer in observers.iter_mut() {
+                observer.notify(&event);
+            }
+        };
 
-                while main_monkey.has_item() {
-                    main_monkey.inspect_item(is_part1);
+        for _ in 0..rounds {
+            self.rounds_played += 1;
+            let round = self.rounds_played;
+            notify(GameEvent::RoundStarted { round });
+
+            for monkey_idx in 0..self.monkeys.len() {
+                let monkey_id = self.monkeys[monkey_idx].id;
+                notify(GameEvent::TurnStarted {
+                    round,
+                    monkey: monkey_id,
+                });
+
+                let (target_true, target_false) = self.throw_targets[monkey_idx];
+
+                while self.monkeys[monkey_idx].has_item() {
+                    let main_monkey = &mut self.monkeys[monkey_idx];
+                    let worry_level = main_monkey.items.front().unwrap().worry_level;
+                    let (after_operation, after_relief) = main_monkey
+                        .inspect_item(&*self.relief, self.worry_modulus)
+                        .map_err(|error| GameError::Evaluation {
+                            round,
+                            monkey: monkey_id,
+                            error,
+                        })?;
+                    notify(GameEvent::Inspected {
+                        round,
+                        monkey: monkey_id,
+                        worry_level,
+                        after_operation,
+                        after_relief,
+                    });
                     let throwing_item = main_monkey.throw_next_item();
 
-                    if throwing_item.test_result {
-                        println!(
-                            "Monkey {} throws item {} to monkey {}.",
-                            monkey_id, throwing_item.item.worry_level, target_monkey_true.id
-                        );
-                        target_monkey_true.catch_item(throwing_item.item);
+                    let target_monkey = if throwing_item.test_result {
+                        &mut self.monkeys[target_true]
                     } else {
-                        println!(
-                            "Monkey {} throws item {} to monkey {}.",
-                            monkey_id, throwing_item.item.worry_level, target_monkey_false.id
-                        );
-                        target_monkey_false.catch_item(throwing_item.item);
-                    }
+                        &mut self.monkeys[target_false]
+                    };
+                    let worry_level = throwing_item.item.worry_level;
+                    notify(GameEvent::Thrown {
+                        round,
+                        from: monkey_id,
+                        to: target_monkey.id,
+                        worry_level,
+                    });
+                    target_monkey.catch_item(throwing_item.item);
+                    notify(GameEvent::Caught {
+                        round,
+                        monkey: target_monkey.id,
+                        worry_level,
+                    });
                 }
 
-                println!("Turn of monkey {} finished.", monkey_id);
-                println!("Inspection count: {}", main_monkey.inspection_count);
+                notify(GameEvent::TurnFinished {
+                    round,
+                    monkey: monkey_id,
+                    inspection_count: self.monkeys[monkey_idx].inspection_count,
+                });
             }
 
-            println!("\nRound {} finished. Inspection counts:", idx + 1);
-            for monkey in &self.monkeys {
-                println!(
-                    "Monkey {} inspected items {} times.",
-                    monkey.id, monkey.inspection_count
-                );
-            }
+            notify(GameEvent::RoundFinished {
+                round,
+                inspection_counts: self
+                    .monkeys
+                    .iter()
+                    .map(|monkey| (monkey.id, monkey.inspection_count))
+                    .collect(),
+            });
         }
 
-        self.multiply_two_highest_inspection_counts()
+        Ok(self.multiply_two_highest_inspection_counts())
     }
 
-    fn multiply_two_highest_inspection_counts(&mut self) -> u64 {
-        self.monkeys
-            .sort_by(|a, b| b.inspection_count.cmp(&a.inspection_count));
-
-        let result = self.monkeys.get(0).unwrap().inspection_count
-            * self.monkeys.get(1).unwrap().inspection_count;
+    /// With a single monkey, that is its inspection count.
+    fn multiply_two_highest_inspection_counts(&self) -> u64 {
+        let mut inspection_counts: Vec<u64> = self
+            .monkeys
+            .iter()
+            .map(|monkey| monkey.inspection_count)
+            .collect();
+        inspection_counts.sort_unstable_by_key(|&count| std::cmp::Reverse(count));
 
-        return result;
+        inspection_counts.iter().take(2).product()
     }
 }
 
+/// Prints both parts, or with `--relief` plays a single game with the given relief policy for
+/// `--rounds` rounds, 20 by default. With `--fast`, the games with modular relief are
+/// fast-forwarded instead of being played round by round, with `--log` every step of the games
+/// is printed:
+///
+/// `day11 [--relief div3|lcm|none|div-mod:<k>] [--rounds <n>] [--fast|--log]`
+///
+/// The single game can also be recorded as CSV events, as a Graphviz graph of the throws, or
+/// summarised as a throw-frequency matrix:
+///
+/// `day11 --relief <policy> [--rounds <n>] [--csv <file>] [--dot <file>] [--matrix]`
+///
+/// With `--exact`, a single game is played without relief, keeping the exact worry levels:
+///
+/// `day11 --exact [--rounds <n>]`
 fn main() {
     let input = include_str!("../input.txt");
 
-    println!(
-        "Result of part1: {}",
-        calculate_monkey_business(input, 20, true, None)
-    );
-
-    // For part 2: As all the divisors are pairwise coprime integers, the Chinese Remainder
-    // Theorem can be used.
-    println!(
-        "Result of part2: {}",
-        calculate_monkey_business(input, 10000, false, Some(2 * 3 * 5 * 7 * 11 * 13 * 17 * 19))
-    );
+    let mut args = std::env::args().skip(1);
+    let mut relief: Option<Box<dyn ReliefPolicy>> = None;
+    let mut rounds = 20;
+    let mut fast = false;
+    let mut log = false;
+    let mut csv_file: Option<String> = None;
+    let mut dot_file: Option<String> = None;
+    let mut print_matrix = false;
+    let mut exact = false;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--relief" => relief = Some(parse_relief_arg(args.next())),
+            "--rounds" => rounds = parse_number_arg(&arg, args.next()),
+            "--fast" => fast = true,
+            "--log" => log = true,
+            "--csv" => csv_file = Some(args.next().expect("--csv requires a file")),
+            "--dot" => dot_file = Some(args.next().expect("--dot requires a file")),
+            "--matrix" => print_matrix = true,
+            "--exact" => exact = true,
+            _ => panic!("Unknown argument {}", arg),
+        }
+    }
+
+    if exact {
+        let game = new_game_from_input(input, Box::new(NoRelief));
+        let mut exact_game = ExactGame::new(&game);
+        let monkey_business = exact_game.play(rounds);
+
+        for (idx, monkey) in game.monkeys.iter().enumerate() {
+            let max_bits = exact_game
+                .worry_levels(idx)
+                .iter()
+                .map(|worry_level| worry_level.bit_len())
+                .max();
+            let items = match max_bits {
+                Some(max_bits) => format!("holds worry levels of up to {} bits", max_bits),
+                None => String::from("holds no items"),
+            };
+            println!(
+                "Monkey {} inspected items {} times and {}.",
+                monkey.id,
+                exact_game.inspection_counts()[idx],
+                items
+            );
+        }
+        match monkey_business {
+            Ok(monkey_business) => println!("Monkey business without relief: {}", monkey_business),
+            Err(err) => println!("Game aborted in {}", err),
+        }
+        return;
+    }
+
+    if csv_file.is_some() || dot_file.is_some() || print_matrix {
+        let relief = relief.expect("Recording a game requires --relief");
+        assert!(!fast, "Fast-forwarded games cannot be recorded");
+        record_game(input, rounds, relief, csv_file, dot_file, print_matrix);
+        return;
+    }
+
+    if fast {
+        assert!(!log, "Fast-forwarded games cannot be logged");
+        assert!(
+            relief.as_ref().is_none_or(|relief| relief.is_modular()),
+            "--fast requires --relief lcm or div-mod:<k>"
+        );
+    }
+    let monkey_business = |rounds, relief: Box<dyn ReliefPolicy>| {
+        if log {
+            return new_game_from_input(input, relief)
+                .play_observed(rounds, &mut [&mut GameLog])
+                .unwrap_or_else(|err| panic!("Game aborted in {}", err));
+        }
+        if !fast {
+            return calculate_monkey_business(input, rounds, relief);
+        }
+
+        // Part 1 cannot be fast-forwarded, but is short enough to play.
+        let mut game = new_game_from_input(input, relief);
+        let monkey_business = if game.relief.is_modular() {
+            game.fast_forward(rounds)
+        } else {
+            game.play(rounds)
+        };
+        monkey_business.unwrap_or_else(|err| panic!("Game aborted in {}", err))
+    };
+
+    match relief {
+        Some(relief) => println!("Monkey business: {}", monkey_business(rounds, relief)),
+        None => {
+            println!(
+                "Result of part1: {}",
+                monkey_business(20, Box::new(DivideByThree))
+            );
+            println!(
+                "Result of part2: {}",
+                monkey_business(10000, Box::new(ModuloLcm))
+            );
+        }
+    }
 }
 
-fn calculate_monkey_business(
+fn record_game(
     input: &str,
     rounds: u64,
-    is_part1: bool,
-    divisor: Option<u64>,
-) -> u64 {
-    let monkeys = parse_monkeys_from_input(input, is_part1, divisor);
-    let mut monkey_game = MonkeyGame::new(monkeys, is_part1);
+    relief: Box<dyn ReliefPolicy>,
+    csv_file: Option<String>,
+    dot_file: Option<String>,
+    print_matrix: bool,
+) {
+    let mut game = new_game_from_input(input, relief);
+    let mut event_log = EventLog::default();
+    let mut throw_matrix = ThrowMatrix::new(&game);
+
+    let monkey_business = if csv_file.is_some() {
+        game.play_observed(rounds, &mut [&mut event_log, &mut throw_matrix])
+    } else {
+        game.play_observed(rounds, &mut [&mut throw_matrix])
+    };
+    match monkey_business {
+        Ok(monkey_business) => println!("Monkey business: {}", monkey_business),
+        Err(err) => println!("Game aborted in {}", err),
+    }
 
-    monkey_game.play(rounds)
+    if let Some(path) = csv_file {
+        event_log.write_csv(create_file(&path)).unwrap();
+    }
+    if let Some(path) = dot_file {
+        throw_matrix.write_dot(create_file(&path)).unwrap();
+    }
+    if print_matrix {
+        println!("Throws from (rows) to (columns):\n{}", throw_matrix);
+    }
 }
 
-fn parse_monkeys_from_input(input: &str, is_part1: bool, divisor: Option<u64>) -> Vec<Monkey> {
-    return input
-        .split_terminator("\n\n")
-        .map(|monkey| parse_monkey_from_input(monkey, is_part1, divisor))
-        .collect();
+fn create_file(path: &str) -> BufWriter<File> {
+    BufWriter::new(File::create(path).unwrap_or_else(|err| panic!("{}: {}", path, err)))
 }
 
-fn parse_monkey_from_input(monkey: &str, is_part1: bool, divisor: Option<u64>) -> Monkey {
-    // Monkey 0:
-    //   Starting items: 79, 98
-    //   Operation: new = old * 19
-    //   Test: divisible by 23
-    //     If true: throw to monkey 2
-    //     If false: throw to monkey 3
-    let mut input_iter = monkey.lines();
-
-    let monkey_id = input_iter
-        .next()
-        .unwrap()
-        .split_whitespace()
-        .nth(1)
-        .unwrap()
-        .chars()
-        .nth(0)
-        .unwrap()
-        .to_digit(10)
-        .unwrap();
-
-    let initial_items = input_iter
-        .next()
-        .unwrap()
-        .split_whitespace()
-        .skip(2)
-        .map(|mut item| {
-            match item.strip_suffix(",") {
-                Some(v) => item = v,
-                None => (),
-            };
+fn parse_relief_arg(value: Option<String>) -> Box<dyn ReliefPolicy> {
+    match value.as_deref() {
+        Some("div3") => Box::new(DivideByThree),
+        Some("lcm") => Box::new(ModuloLcm),
+        Some("none") => Box::new(NoRelief),
+        Some(policy) if policy.starts_with("div-mod:") => {
+            Box::new(DivideThenModulo(parse_number_arg(
+                "--relief div-mod:",
+                Some(policy["div-mod:".len()..].to_string()),
+            )))
+        }
+        _ => panic!("--relief requires div3, lcm, none or div-mod:<k>"),
+    }
+}
 
-            return Item {
-                worry_level: item.parse().unwrap(),
-                divisor_product: divisor,
-            };
-        })
-        .collect();
-
-    let worry_operation = parse_worry_operation(input_iter.next().unwrap(), is_part1);
-
-    let divisor = input_iter
-        .next()
-        .unwrap()
-        .split_whitespace()
-        .nth(3)
-        .unwrap()
-        .parse()
-        .unwrap();
-
-    let target_monkey_id_true = input_iter
-        .next()
-        .unwrap()
-        .split_whitespace()
-        .nth(5)
-        .unwrap()
-        .parse()
-        .unwrap();
-    let target_monkey_id_false = input_iter
-        .next()
-        .unwrap()
-        .split_whitespace()
-        .nth(5)
-        .unwrap()
-        .parse()
-        .unwrap();
-
-    return Monkey::new(
-        monkey_id.into(),
-        initial_items,
-        worry_operation,
-        divisor,
-        target_monkey_id_true,
-        target_monkey_id_false,
-    );
+fn parse_number_arg(option: &str, value: Option<String>) -> u64 {
+    value
+        .and_then(|value| value.parse().ok())
+        .unwrap_or_else(|| panic!("{} requires a number", option))
 }
 
-fn parse_worry_operation(input: &str, is_part1: bool) -> WorryOperation {
-    let mut worry_operation_input = input
-        .split_once(" = ")
-        .unwrap()
-        .1
-        .split_whitespace()
-        .skip(1);
-
-    let operator = worry_operation_input
-        .next()
-        .unwrap()
-        .chars()
-        .nth(0)
-        .unwrap();
-
-    let operand_str = worry_operation_input.next().unwrap();
-
-    if operator == '+' {
-        if is_part1 {
-            return WorryOperation::AddConstant(operand_str.parse().unwrap());
-        } else {
-            return WorryOperation::AddConstantPart2(operand_str.parse().unwrap());
-        }
-    } else if operator == '*' {
-        if operand_str == "old" {
-            if is_part1 {
-                return WorryOperation::Square;
-            } else {
-                return WorryOperation::SquarePart2;
-            }
-        } else {
-            if is_part1 {
-                return WorryOperation::MultiplyByConstant(operand_str.parse().unwrap());
-            } else {
-                return WorryOperation::MultiplyByConstantPart2(operand_str.parse().unwrap());
-            }
-        }
-    } else {
-        panic!("Unknown operator: {}", operator);
+/// Panics with the reason if the notes are invalid or the game cannot be played.
+fn calculate_monkey_business(input: &str, rounds: u64, relief: Box<dyn ReliefPolicy>) -> u64 {
+    let mut monkey_game = new_game_from_input(input, relief);
+
+    monkey_game
+        .play(rounds)
+        .unwrap_or_else(|err| panic!("Game aborted in {}", err))
+}
+
+/// Panics with the reason if the notes are invalid.
+fn parse_monkeys_from_input(input: &str) -> Vec<Monkey> {
+    parse_monkeys(input).unwrap_or_else(|err| panic!("Invalid notes, {}", err))
+}
+
+/// Panics with the reason if the notes are invalid or do not work with the relief policy.
+fn new_game_from_input(input: &str, relief: Box<dyn ReliefPolicy>) -> MonkeyGame {
+    MonkeyGame::new(parse_monkeys_from_input(input), relief)
+        .unwrap_or_else(|err| panic!("Invalid game, {}", err))
+}
+
+/// The least common multiple of the moduli of all test conditions. Every modulus divides it, so
+/// worry levels can be kept modulo the LCM without changing the outcome of any test, as long as
+/// the operations only add and multiply. Comparisons, subtractions and divisions depend on the
+/// actual worry levels and are not preserved, so [`MonkeyGame::new`] rejects them under modular
+/// relief.
+fn worry_modulus(monkeys: &[Monkey]) -> u64 {
+    monkeys
+        .iter()
+        .filter_map(|monkey| monkey.test.modulus())
+        .fold(1, least_common_multiple)
+}
+
+fn least_common_multiple(a: u64, b: u64) -> u64 {
+    a / greatest_common_divisor(a, b) * b
+}
+
+fn greatest_common_divisor(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
     }
+    a
 }
 
 #[cfg(test)]
@@ -392,37 +500,21 @@ mod tests {
 
     #[test]
     fn test_parse_monkey() {
-        let monkey_input = r"Monkey 0:
-  Starting items: 79, 98
-  Operation: new = old * 19
-  Test: divisible by 23
-    If true: throw to monkey 2
-    If false: throw to monkey 3";
-
         let want = Monkey::new(
             0,
-            VecDeque::from(vec![
-                Item {
-                    worry_level: 79,
-                    divisor_product: None,
-                },
-                Item {
-                    worry_level: 98,
-                    divisor_product: None,
-                },
-            ]),
-            WorryOperation::MultiplyByConstant(19),
-            23,
+            VecDeque::from(vec![Item { worry_level: 79 }, Item { worry_level: 98 }]),
+            Expression::parse("old * 19").unwrap(),
+            TestCondition::DivisibleBy(23),
             2,
             3,
         );
 
-        let got = parse_monkey_from_input(monkey_input, false, None);
+        let got = parse_monkeys_from_input(include_str!("../test.txt")).remove(0);
 
         assert_eq!(got.id, want.id);
         assert_eq!(
-            got.items.get(0).unwrap().worry_level,
-            want.items.get(0).unwrap().worry_level
+            got.items.front().unwrap().worry_level,
+            want.items.front().unwrap().worry_level
         );
         assert_eq!(
             got.items.get(1).unwrap().worry_level,
@@ -436,20 +528,130 @@ mod tests {
             got.throw_to_monkey_id_if_true,
             want.throw_to_monkey_id_if_true
         );
-        assert_eq!(got.test_divisor, want.test_divisor);
+        assert_eq!(got.test, want.test);
+        assert_eq!(got.worry_operation, want.worry_operation);
+    }
+
+    #[test]
+    fn test_expression_operations_and_conditions() {
+        let input = include_str!("../test.txt")
+            .replace("new = old * 19", "new = (old * 3 + 7) * old % 1000")
+            .replace("divisible by 19", "greater than 500")
+            .replace("divisible by 13", "even");
+
+        let mut monkeys = parse_monkeys_from_input(&input);
+        assert_eq!(
+            Expression::parse("((old * 3) + 7) * old % 1000").unwrap(),
+            monkeys[0].worry_operation
+        );
+        assert_eq!(TestCondition::GreaterThan(500), monkeys[1].test);
+        assert_eq!(TestCondition::Even, monkeys[2].test);
+        assert_eq!(23 * 2 * 17, worry_modulus(&monkeys));
+
+        // Monkey 0 turns 79 into (79 * 3 + 7) * 79 % 1000 = 276, which relief divides to 92,
+        // a multiple of 23.
+        let modulus = worry_modulus(&monkeys);
+        monkeys[0].inspect_item(&DivideByThree, modulus).unwrap();
+        let throwing_item = monkeys[0].throw_next_item();
+        assert_eq!(92, throwing_item.item.worry_level);
+        assert!(throwing_item.test_result);
+    }
+
+    #[test]
+    fn test_multi_digit_ids_take_turns_in_order() {
+        // Renumbered to 10 to 13, with monkey 10 listed last.
+        let input = include_str!("../test.txt");
+        let mut renumbered = input.split("\n\n").collect::<Vec<_>>();
+        renumbered.rotate_left(1);
+        let renumbered = renumbered
+            .join("\n\n")
+            .replace("Monkey ", "Monkey 1")
+            .replace("monkey ", "monkey 1");
+
+        let mut game = new_game_from_input(&renumbered, Box::new(DivideByThree));
+        assert_eq!(
+            vec![10, 11, 12, 13],
+            game.monkeys.iter().map(|m| m.id).collect::<Vec<_>>()
+        );
+        assert_eq!(Ok(10605), game.play(20));
+        assert_eq!(
+            Ok(calculate_monkey_business(
+                input,
+                40,
+                Box::new(DivideByThree)
+            )),
+            game.play(20)
+        );
+    }
+
+    #[test]
+    fn test_modular_relief_rejects_incompatible_monkeys() {
+        let game = |input: &str, relief: Box<dyn ReliefPolicy>| {
+            MonkeyGame::new(parse_monkeys_from_input(input), relief).map(|_| ())
+        };
+        let comparing = include_str!("../test.txt").replace("divisible by 19", "greater than 500");
+        let subtracting = include_str!("../test.txt").replace("old + 6", "old - 6");
+
+        assert_eq!(
+            Err(GameError::TestWithoutModulus {
+                monkey: 1,
+                test: TestCondition::GreaterThan(500),
+            }),
+            game(&comparing, Box::new(ModuloLcm))
+        );
+        assert_eq!(
+            "monkey 1: operation '(old - 6)' cannot be combined with modular relief, which only \
+             allows '+' and '*'",
+            game(&subtracting, Box::new(DivideThenModulo(2)))
+                .unwrap_err()
+                .to_string()
+        );
+        assert_eq!(Ok(()), game(&comparing, Box::new(DivideByThree)));
+        assert_eq!(Ok(()), game(&subtracting, Box::new(NoRelief)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid game, monkey 1: test 'greater than 500' cannot be")]
+    fn test_modular_relief_with_comparisons_panics() {
+        let input = include_str!("../test.txt").replace("divisible by 19", "greater than 500");
+        calculate_monkey_business(&input, 20, Box::new(ModuloLcm));
+    }
+
+    #[test]
+    fn test_failing_operation_stops_the_game() {
+        // Monkey 0 starts with 79 and 98, so its first inspection goes negative.
+        let input = include_str!("../test.txt").replace("new = old * 19", "new = old - 80");
+        let mut game = new_game_from_input(&input, Box::new(DivideByThree));
+
+        let err = game.play_observed(20, &mut []).unwrap_err();
+        assert_eq!(
+            "round 1, monkey 0: worry level out of range evaluating 79 - 80",
+            err.to_string()
+        );
+        assert_eq!(1, game.monkeys[0].inspection_count);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid notes, line 5: monkey 0 throws to itself")]
+    fn test_invalid_notes() {
+        let input = include_str!("../test.txt").replacen("monkey 2", "monkey 0", 1);
+        calculate_monkey_business(&input, 20, Box::new(DivideByThree));
     }
 
     #[test]
     fn test_part1() {
         let input = include_str!("../test.txt");
-        assert_eq!(calculate_monkey_business(input, 20, true, None), 10605);
+        assert_eq!(
+            calculate_monkey_business(input, 20, Box::new(DivideByThree)),
+            10605
+        );
     }
 
     #[test]
     fn test_part2_one_round() {
         let input = include_str!("../test.txt");
         assert_eq!(
-            calculate_monkey_business(input, 1, false, Some(13 * 17 * 19 * 23)),
+            calculate_monkey_business(input, 1, Box::new(ModuloLcm)),
             4 * 6
         );
     }
@@ -458,7 +660,7 @@ mod tests {
     fn test_part2_twenty_rounds() {
         let input = include_str!("../test.txt");
         assert_eq!(
-            calculate_monkey_business(input, 20, false, Some(13 * 17 * 19 * 23)),
+            calculate_monkey_business(input, 20, Box::new(ModuloLcm)),
             99 * 103
         );
     }
@@ -467,8 +669,54 @@ mod tests {
     fn test_part2_all_rounds() {
         let input = include_str!("../test.txt");
         assert_eq!(
-            calculate_monkey_business(input, 10000, false, Some(13 * 17 * 19 * 23)),
+            calculate_monkey_business(input, 10000, Box::new(ModuloLcm)),
             2713310158
         );
     }
+
+    #[test]
+    fn test_worry_modulus_is_lcm_of_divisors() {
+        let monkeys = parse_monkeys_from_input(include_str!("../test.txt"));
+        assert_eq!(23 * 19 * 13 * 17, worry_modulus(&monkeys));
+
+        assert_eq!(36, [4, 6, 6, 9].into_iter().fold(1, least_common_multiple));
+    }
+
+    #[test]
+    fn test_part2_with_shared_divisor_factors() {
+        // Divisors 4, 6, 2 and 9 share factors and repeat the factor 2, their LCM is 36.
+        let input = include_str!("../test.txt")
+            .replace("divisible by 23", "divisible by 4")
+            .replace("divisible by 19", "divisible by 6")
+            .replace("divisible by 13", "divisible by 2")
+            .replace("divisible by 17", "divisible by 9");
+
+        let monkeys = parse_monkeys_from_input(&input);
+        assert_eq!(36, worry_modulus(&monkeys));
+
+        // Any common multiple keeps the tests intact, so the product gives the same result.
+        struct ModuloProduct;
+        impl ReliefPolicy for ModuloProduct {
+            fn relieve(&self, worry_level: u64, _worry_modulus: u64) -> u64 {
+                worry_level % (4 * 6 * 2 * 9)
+            }
+        }
+        assert_eq!(
+            calculate_monkey_business(&input, 1000, Box::new(ModuloProduct)),
+            calculate_monkey_business(&input, 1000, Box::new(ModuloLcm))
+        );
+    }
+
+    #[test]
+    fn test_additive_monkeys_without_relief() {
+        // Without multiplications, worry levels stay small enough to need no relief at all.
+        let input = include_str!("../test.txt")
+            .replace("old * 19", "old + 19")
+            .replace("old * old", "old + 1");
+
+        assert_eq!(
+            calculate_monkey_business(&input, 20, Box::new(NoRelief)),
+            calculate_monkey_business(&input, 20, Box::new(ModuloLcm))
+        );
+    }
 }
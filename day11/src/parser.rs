@@ -0,0 +1,425 @@
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+
+use crate::condition::TestCondition;
+use crate::expression::{Expression, ExpressionError};
+use crate::{Item, Monkey};
+
+/// Where the notes are invalid, `line` counting from 1.
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub kind: ParseErrorKind,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ParseErrorKind {
+    NoMonkeys,
+    /// A field before the first `Monkey <id>:` line.
+    ExpectedMonkeyHeader,
+    InvalidMonkeyId(String),
+    DuplicateMonkey(u64),
+    UnknownField(String),
+    DuplicateField(&'static str),
+    MissingField {
+        monkey: u64,
+        field: &'static str,
+    },
+    InvalidNumber(String),
+    InvalidOperation(ExpressionError),
+    UnknownTestCondition(String),
+    /// Anything but `throw to monkey <id>`.
+    InvalidThrow(String),
+    UnknownThrowTarget {
+        monkey: u64,
+        target: u64,
+    },
+    ThrowsToItself(u64),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            ParseErrorKind::NoMonkeys => write!(f, "no monkeys in the notes"),
+            ParseErrorKind::ExpectedMonkeyHeader => write!(f, "expected 'Monkey <id>:'"),
+            ParseErrorKind::InvalidMonkeyId(id) => write!(f, "invalid monkey id '{}'", id),
+            ParseErrorKind::DuplicateMonkey(id) => write!(f, "monkey {} is defined twice", id),
+            ParseErrorKind::UnknownField(field) => write!(f, "unknown field '{}'", field),
+            ParseErrorKind::DuplicateField(field) => write!(f, "'{}' is given twice", field),
+            ParseErrorKind::MissingField { monkey, field } => {
+                write!(f, "monkey {} has no '{}'", monkey, field)
+            }
+            ParseErrorKind::InvalidNumber(number) => write!(f, "invalid number '{}'", number),
+            ParseErrorKind::InvalidOperation(err) => write!(f, "invalid operation, {}", err),
+            ParseErrorKind::UnknownTestCondition(condition) => {
+                write!(f, "unknown test condition '{}'", condition)
+            }
+            ParseErrorKind::InvalidThrow(throw) => {
+                write!(f, "expected 'throw to monkey <id>', found '{}'", throw)
+            }
+            ParseErrorKind::UnknownThrowTarget { monkey, target } => {
+                write!(f, "monkey {} throws to unknown monkey {}", monkey, target)
+            }
+            ParseErrorKind::ThrowsToItself(monkey) => {
+                write!(f, "monkey {} throws to itself", monkey)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+const STARTING_ITEMS: &str = "Starting items";
+const OPERATION: &str = "Operation";
+const TEST: &str = "Test";
+const IF_TRUE: &str = "If true";
+const IF_FALSE: &str = "If false";
+
+/// The fields of one monkey as far as they have been read. Throw targets keep their line for
+/// validating them once all monkeys are known.
+struct MonkeyNotes {
+    id: u64,
+    line: usize,
+    items: Option<VecDeque<Item>>,
+    operation: Option<Expression>,
+    test: Option<TestCondition>,
+    if_true: Option<(usize, u64)>,
+    if_false: Option<(usize, u64)>,
+}
+
+impl MonkeyNotes {
+    fn required<T>(&self, field: Option<T>, name: &'static str) -> Result<T, ParseError> {
+        field.ok_or(ParseError {
+            line: self.line,
+            kind: ParseErrorKind::MissingField {
+                monkey: self.id,
+                field: name,
+            },
+        })
+    }
+}
+
+/// Parses the notes and checks that every monkey throws to two other monkeys that exist.
+///
+/// Monkeys start with a `Monkey <id>:` line, followed by their fields in any order. Blank lines,
+/// indentation and trailing whitespace are ignored. Like the header, field names are case
+/// sensitive.
+pub fn parse_monkeys(input: &str) -> Result<Vec<Monkey>, ParseError> {
+    let mut notes: Vec<MonkeyNotes> = Vec::new();
+
+    for (idx, raw_line) in input.lines().enumerate() {
+        let line_number = idx + 1;
+        let line = raw_line.trim();
+        let error = |kind| ParseError {
+            line: line_number,
+            kind,
+        };
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(id) = line.strip_prefix("Monkey") {
+            let id = id.trim().trim_end_matches(':').trim();
+            let id = id
+                .parse()
+                .map_err(|_| error(ParseErrorKind::InvalidMonkeyId(id.to_string())))?;
+            if notes.iter().any(|monkey| monkey.id == id) {
+                return Err(error(ParseErrorKind::DuplicateMonkey(id)));
+            }
+            notes.push(MonkeyNotes {
+                id,
+                line: line_number,
+                items: None,
+                operation: None,
+                test: None,
+                if_true: None,
+                if_false: None,
+            });
+            continue;
+        }
+
+        let monkey = notes
+            .last_mut()
+            .ok_or_else(|| error(ParseErrorKind::ExpectedMonkeyHeader))?;
+        let (field, value) = line
+            .split_once(':')
+            .ok_or_else(|| error(ParseErrorKind::UnknownField(line.to_string())))?;
+        let value = value.trim();
+
+        let field = field.trim();
+        let name = [STARTING_ITEMS, OPERATION, TEST, IF_TRUE, IF_FALSE]
+            .into_iter()
+            .find(|name| *name == field)
+            .ok_or_else(|| error(ParseErrorKind::UnknownField(field.to_string())))?;
+        let is_duplicate = match name {
+            STARTING_ITEMS => monkey.items.is_some(),
+            OPERATION => monkey.operation.is_some(),
+            TEST => monkey.test.is_some(),
+            IF_TRUE => monkey.if_true.is_some(),
+            _ => monkey.if_false.is_some(),
+        };
+        if is_duplicate {
+            return Err(error(ParseErrorKind::DuplicateField(name)));
+        }
+
+        match name {
+            STARTING_ITEMS => {
+                let items = value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|item| !item.is_empty())
+                    .map(|item| {
+                        let worry_level = parse_number(item).map_err(error)?;
+                        Ok(Item { worry_level })
+                    })
+                    .collect::<Result<_, _>>()?;
+                monkey.items = Some(items);
+            }
+            OPERATION => {
+                // `value` and the expression run until the end of the line, which gives their
+                // offset within it for reporting columns of the whole line.
+                let offset_of = |part: &str| raw_line.trim_end().len() - part.len();
+                let expression = value
+                    .strip_prefix("new")
+                    .map(str::trim_start)
+                    .and_then(|rest| rest.strip_prefix('='))
+                    .map(str::trim_start)
+                    .ok_or_else(|| {
+                        error(ParseErrorKind::InvalidOperation(ExpressionError {
+                            column: offset_of(value) + 1,
+                            message: String::from("expected 'new = <expression>'"),
+                        }))
+                    })?;
+                let expression = Expression::parse(expression).map_err(|mut err| {
+                    err.column += offset_of(expression);
+                    error(ParseErrorKind::InvalidOperation(err))
+                })?;
+                monkey.operation = Some(expression);
+            }
+            TEST => {
+                let condition = TestCondition::parse(value).ok_or_else(|| {
+                    error(ParseErrorKind::UnknownTestCondition(value.to_string()))
+                })?;
+                monkey.test = Some(condition);
+            }
+            _ => {
+                let target = parse_throw(value).map_err(error)?;
+                if name == IF_TRUE {
+                    monkey.if_true = Some((line_number, target));
+                } else {
+                    monkey.if_false = Some((line_number, target));
+                }
+            }
+        }
+    }
+
+    if notes.is_empty() {
+        return Err(ParseError {
+            line: 1,
+            kind: ParseErrorKind::NoMonkeys,
+        });
+    }
+
+    let ids: HashSet<u64> = notes.iter().map(|monkey| monkey.id).collect();
+    let mut monkeys = Vec::with_capacity(notes.len());
+    for mut monkey in notes {
+        let id = monkey.id;
+        let items = monkey.items.take().unwrap_or_default();
+        let operation = monkey.required(monkey.operation.clone(), OPERATION)?;
+        let test = monkey.required(monkey.test, TEST)?;
+        let if_true = monkey.required(monkey.if_true, IF_TRUE)?;
+        let if_false = monkey.required(monkey.if_false, IF_FALSE)?;
+
+        for (target_line, target) in [if_true, if_false] {
+            let kind = if target == id {
+                ParseErrorKind::ThrowsToItself(id)
+            } else if !ids.contains(&target) {
+                ParseErrorKind::UnknownThrowTarget { monkey: id, target }
+            } else {
+                continue;
+            };
+            return Err(ParseError {
+                line: target_line,
+                kind,
+            });
+        }
+
+        monkeys.push(Monkey::new(
+            id, items, operation, test, if_true.1, if_false.1,
+        ));
+    }
+
+    Ok(monkeys)
+}
+
+fn parse_number(number: &str) -> Result<u64, ParseErrorKind> {
+    number
+        .parse()
+        .map_err(|_| ParseErrorKind::InvalidNumber(number.to_string()))
+}
+
+fn parse_throw(value: &str) -> Result<u64, ParseErrorKind> {
+    let words: Vec<&str> = value.split_whitespace().collect();
+    match words.as_slice() {
+        ["throw", "to", "monkey", target] => parse_number(target),
+        _ => Err(ParseErrorKind::InvalidThrow(value.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_error(input: &str) -> ParseError {
+        parse_monkeys(input).unwrap_err()
+    }
+
+    #[test]
+    fn test_parse_puzzle_notes() {
+        let monkeys = parse_monkeys(include_str!("../test.txt")).unwrap();
+
+        assert_eq!(4, monkeys.len());
+        assert_eq!(3, monkeys[3].id);
+        assert_eq!(
+            vec![74],
+            monkeys[3]
+                .items
+                .iter()
+                .map(|item| item.worry_level)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(TestCondition::DivisibleBy(17), monkeys[3].test);
+        assert_eq!(
+            (0, 1),
+            (
+                monkeys[3].throw_to_monkey_id_if_true,
+                monkeys[3].throw_to_monkey_id_if_false
+            )
+        );
+    }
+
+    #[test]
+    fn test_tolerant_layout_and_multi_digit_ids() {
+        let input = "\
+Monkey 10:
+  Starting items: 5,6 ,7
+  If false: throw to monkey 12
+  Operation: new=old*2
+  Test: even
+  If true:   throw to monkey 12
+
+
+Monkey 12
+  Starting items:
+  Operation: new = old + 1
+  Test: divisible by 3
+  If true: throw to monkey 10
+  If false: throw to monkey 10
+";
+        let monkeys = parse_monkeys(input).unwrap();
+
+        assert_eq!(
+            vec![10, 12],
+            monkeys.iter().map(|monkey| monkey.id).collect::<Vec<_>>()
+        );
+        assert_eq!(3, monkeys[0].items.len());
+        assert_eq!(12, monkeys[0].throw_to_monkey_id_if_false);
+        assert_eq!(
+            Expression::parse("old * 2").unwrap(),
+            monkeys[0].worry_operation
+        );
+        assert!(monkeys[1].items.is_empty());
+    }
+
+    #[test]
+    fn test_invalid_throw_targets() {
+        let monkey = |id, target| {
+            format!(
+                "Monkey {}:\n  Starting items: 1\n  Operation: new = old\n  Test: odd\n  \
+                 If true: throw to monkey {}\n  If false: throw to monkey {}\n",
+                id, target, target
+            )
+        };
+
+        let err = parse_error(&format!("{}\n{}", monkey(0, 1), monkey(1, 1)));
+        assert_eq!(
+            ParseError {
+                line: 12,
+                kind: ParseErrorKind::ThrowsToItself(1)
+            },
+            err
+        );
+
+        let err = parse_error(&format!("{}\n{}", monkey(0, 7), monkey(1, 0)));
+        assert_eq!(
+            "line 5: monkey 0 throws to unknown monkey 7",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let input = include_str!("../test.txt");
+
+        assert_eq!(ParseErrorKind::NoMonkeys, parse_error("\n\n").kind);
+        assert_eq!(
+            ParseError {
+                line: 1,
+                kind: ParseErrorKind::ExpectedMonkeyHeader
+            },
+            parse_error("  Starting items: 1")
+        );
+        assert_eq!(
+            "line 1: invalid monkey id 'one'",
+            parse_error(&input.replacen("Monkey 0:", "Monkey one:", 1)).to_string()
+        );
+        assert_eq!(
+            "line 8: monkey 0 is defined twice",
+            parse_error(&input.replacen("Monkey 1:", "Monkey 0:", 1)).to_string()
+        );
+        assert_eq!(
+            "line 2: invalid number '9x'",
+            parse_error(&input.replacen("79, 98", "79, 9x", 1)).to_string()
+        );
+        assert_eq!(
+            "line 3: invalid operation, column 24: unexpected character '^'",
+            parse_error(&input.replacen("old * 19", "old ^ 19", 1)).to_string()
+        );
+        assert_eq!(
+            "line 3: invalid operation, column 14: expected 'new = <expression>'",
+            parse_error(&input.replacen("new = old * 19", "old * 19", 1)).to_string()
+        );
+        assert_eq!(
+            "line 4: unknown test condition 'prime'",
+            parse_error(&input.replacen("divisible by 23", "prime", 1)).to_string()
+        );
+        assert_eq!(
+            "line 5: expected 'throw to monkey <id>', found 'throw at monkey 2'",
+            parse_error(&input.replacen("throw to monkey 2", "throw at monkey 2", 1)).to_string()
+        );
+        assert_eq!(
+            "line 6: 'If true' is given twice",
+            parse_error(&input.replacen("If false", "If true", 1)).to_string()
+        );
+        assert_eq!(
+            "line 1: monkey 0 has no 'If false'",
+            parse_error(&input.replacen("    If false: throw to monkey 3\n", "", 1)).to_string()
+        );
+        assert_eq!(
+            "line 2: unknown field 'Items'",
+            parse_error(&input.replacen("Starting items", "Items", 1)).to_string()
+        );
+        assert_eq!(
+            "line 2: unknown field 'starting items'",
+            parse_error(&input.replacen("Starting items", "starting items", 1)).to_string()
+        );
+        assert_eq!(
+            ParseError {
+                line: 1,
+                kind: ParseErrorKind::ExpectedMonkeyHeader
+            },
+            parse_error(&input.replacen("Monkey 0:", "monkey 0:", 1))
+        );
+    }
+}
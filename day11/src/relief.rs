@@ -0,0 +1,77 @@
+/// How a monkey's boredom with an item changes its worry level after the inspection.
+///
+/// `worry_modulus` is the least common multiple of all test divisors. Every divisor divides it,
+/// so reducing a worry level modulo `worry_modulus` never changes the outcome of a test.
+pub trait ReliefPolicy {
+    fn relieve(&self, worry_level: u64, worry_modulus: u64) -> u64;
+
+    /// Whether relieved worry levels are always reduced modulo `worry_modulus`, which only keeps
+    /// the game intact if every test and operation is compatible with the modulus.
+    fn is_modular(&self) -> bool {
+        false
+    }
+}
+
+/// Part 1: the worry level is divided by 3 and rounded down.
+pub struct DivideByThree;
+
+impl ReliefPolicy for DivideByThree {
+    fn relieve(&self, worry_level: u64, _worry_modulus: u64) -> u64 {
+        worry_level / 3
+    }
+}
+
+/// Part 2: no relief, the worry level is only kept small modulo the LCM of the divisors.
+pub struct ModuloLcm;
+
+impl ReliefPolicy for ModuloLcm {
+    fn relieve(&self, worry_level: u64, worry_modulus: u64) -> u64 {
+        worry_level % worry_modulus
+    }
+
+    fn is_modular(&self) -> bool {
+        true
+    }
+}
+
+/// No relief at all. Worry levels grow quickly and overflow after a few rounds unless the
+/// operations only add, [`crate::exact::ExactGame`] keeps them exactly instead.
+pub struct NoRelief;
+
+impl ReliefPolicy for NoRelief {
+    fn relieve(&self, worry_level: u64, _worry_modulus: u64) -> u64 {
+        worry_level
+    }
+}
+
+/// Divides the worry level by `k`, then reduces it modulo the LCM of the divisors. Dividing
+/// breaks the congruences the modulus relies on, so results can differ from dividing alone.
+pub struct DivideThenModulo(pub u64);
+
+impl ReliefPolicy for DivideThenModulo {
+    fn relieve(&self, worry_level: u64, worry_modulus: u64) -> u64 {
+        (worry_level / self.0) % worry_modulus
+    }
+
+    fn is_modular(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relief_policies() {
+        assert_eq!(500, DivideByThree.relieve(1501, 96577));
+        assert_eq!(1501 % 96, ModuloLcm.relieve(1501, 96));
+        assert_eq!(1501, NoRelief.relieve(1501, 96));
+        assert_eq!((1501 / 5) % 96, DivideThenModulo(5).relieve(1501, 96));
+
+        assert!(!DivideByThree.is_modular());
+        assert!(ModuloLcm.is_modular());
+        assert!(!NoRelief.is_modular());
+        assert!(DivideThenModulo(5).is_modular());
+    }
+}
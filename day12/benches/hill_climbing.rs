@@ -0,0 +1,55 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use day12::{find_shortest_path, parse_board, Algorithm, MovementRules, StartingPos, UnitCost};
+
+/// A height map that slowly rises from `S` in the top left corner to `E` in the bottom right
+/// one, with random dips of one level, generated with a xorshift so that every run sees the same
+/// map.
+fn generate_height_map(width: usize, height: usize) -> String {
+    let mut state: u64 = 0x2545_F491_4F6C_DD1D;
+    let mut rows: Vec<String> = (0..height)
+        .map(|row| {
+            (0..width)
+                .map(|col| {
+                    state ^= state << 13;
+                    state ^= state >> 7;
+                    state ^= state << 17;
+                    let level = (row + col) * 26 / (width + height);
+                    let dip = (state % 2) as usize;
+                    (b'a' + level.saturating_sub(dip) as u8) as char
+                })
+                .collect()
+        })
+        .collect();
+    rows[0].replace_range(0..1, "S");
+    rows[height - 1].replace_range(width - 1..width, "E");
+    rows.join("\n")
+}
+
+pub fn criterion_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("hill climbing");
+    group.sample_size(10);
+
+    let height_map = generate_height_map(1000, 1000);
+    assert!(find_shortest_path(&height_map, StartingPos::S).is_some());
+    group.bench_function("part 1 - 1000x1000", |b| {
+        b.iter(|| find_shortest_path(black_box(&height_map), StartingPos::S));
+    });
+    group.bench_function("part 2 - 1000x1000", |b| {
+        b.iter(|| find_shortest_path(black_box(&height_map), StartingPos::AllLowestPoints));
+    });
+
+    let board = parse_board(&height_map);
+    let rules = MovementRules::default();
+    for (name, algorithm) in [("dijkstra", Algorithm::Dijkstra), ("a*", Algorithm::AStar)] {
+        group.bench_function(format!("{} - 1000x1000", name), |b| {
+            b.iter(|| {
+                board.find_cheapest_route(board.start, board.end, &rules, &UnitCost, algorithm)
+            });
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
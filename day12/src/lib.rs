@@ -0,0 +1,385 @@
+use std::collections::VecDeque;
+
+mod pathfinding;
+mod render;
+
+pub use pathfinding::{Algorithm, EffortCost, MoveCost, MovementRules, Route, UnitCost};
+pub use render::Background;
+
+/// `(row, column)`, counted from the top left.
+pub type GridPos = (u32, u32);
+pub type Path = Vec<GridPos>;
+
+pub enum StartingPos {
+    S,
+    AllLowestPoints,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct GridSquare {
+    pub height: u32,
+}
+
+/// Which way the moves of a search go.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SearchDirection {
+    /// Moves as the climber makes them, from the sources outward.
+    Forward,
+    /// Moves backwards, towards the sources, so that the search finds how far every square is
+    /// from reaching one of them.
+    Reverse,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Board {
+    pub start: GridPos,
+    pub end: GridPos,
+    pub grid: Vec<Vec<GridSquare>>,
+}
+
+impl Board {
+    pub fn width(&self) -> u32 {
+        self.grid.first().map_or(0, |row| row.len() as u32)
+    }
+
+    pub fn height(&self) -> u32 {
+        self.grid.len() as u32
+    }
+
+    pub fn get_lowest_points(&self) -> Vec<GridPos> {
+        let mut lowest_points = Vec::new();
+
+        for (y, row) in self.grid.iter().enumerate() {
+            for (x, square) in row.iter().enumerate() {
+                if square.height == 'a' as u32 {
+                    lowest_points.push((y as u32, x as u32));
+                }
+            }
+        }
+
+        lowest_points
+    }
+
+    pub fn get_square_at(&self, pos: &GridPos) -> Option<&GridSquare> {
+        let (row, col) = pos;
+        self.grid
+            .get(*row as usize)
+            .and_then(|row| row.get(*col as usize))
+    }
+
+    /// The squares around `pos` that are on the board: above, below, left and right, and with
+    /// `diagonal` also the four corners.
+    fn neighbours(&self, pos: GridPos, diagonal: bool) -> impl Iterator<Item = GridPos> + '_ {
+        const STRAIGHT: [(i32, i32); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+        const DIAGONAL: [(i32, i32); 4] = [(-1, -1), (-1, 1), (1, -1), (1, 1)];
+
+        let offsets = if diagonal { &DIAGONAL[..] } else { &[] };
+        STRAIGHT
+            .iter()
+            .chain(offsets)
+            .filter_map(move |&(row_offset, col_offset)| {
+                let row = pos.0.checked_add_signed(row_offset)?;
+                let col = pos.1.checked_add_signed(col_offset)?;
+                (row < self.height() && col < self.width()).then_some((row, col))
+            })
+    }
+
+    /// Searches breadth first from all `sources` at once, visiting every square once.
+    pub fn breadth_first(
+        &self,
+        sources: &[GridPos],
+        direction: SearchDirection,
+        rules: &MovementRules,
+    ) -> SearchTree {
+        let mut tree = SearchTree::new(self.width(), self.height());
+        let mut queue = VecDeque::new();
+
+        for &source in sources {
+            if tree.distance(source).is_none() {
+                tree.visit(source, 0, None);
+                queue.push_back(source);
+            }
+        }
+
+        while let Some(pos) = queue.pop_front() {
+            let height = self.get_square_at(&pos).unwrap().height;
+            let distance = tree.distance(pos).unwrap();
+
+            for next in self.neighbours(pos, rules.diagonal) {
+                let next_height = self.get_square_at(&next).unwrap().height;
+                let is_reachable = match direction {
+                    SearchDirection::Forward => rules.allows(height, next_height),
+                    SearchDirection::Reverse => rules.allows(next_height, height),
+                };
+                if is_reachable && tree.distance(next).is_none() {
+                    tree.visit(next, distance + 1, Some(pos));
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        tree
+    }
+}
+
+/// The outcome of a breadth first search: how many steps every square is from the nearest
+/// source, and through which square the search reached it.
+#[derive(Debug, Clone)]
+pub struct SearchTree {
+    width: u32,
+    distances: Vec<Option<u32>>,
+    parents: Vec<Option<GridPos>>,
+}
+
+impl SearchTree {
+    fn new(width: u32, height: u32) -> SearchTree {
+        let size = (width * height) as usize;
+        SearchTree {
+            width,
+            distances: vec![None; size],
+            parents: vec![None; size],
+        }
+    }
+
+    fn index(&self, pos: GridPos) -> usize {
+        let (row, col) = pos;
+        (row * self.width + col) as usize
+    }
+
+    fn visit(&mut self, pos: GridPos, distance: u32, parent: Option<GridPos>) {
+        let idx = self.index(pos);
+        self.distances[idx] = Some(distance);
+        self.parents[idx] = parent;
+    }
+
+    /// `None` if the search never reached `pos`.
+    pub fn distance(&self, pos: GridPos) -> Option<u32> {
+        self.distances[self.index(pos)]
+    }
+
+    /// The square the search came from when it reached `pos`, `None` for sources.
+    pub fn parent(&self, pos: GridPos) -> Option<GridPos> {
+        self.parents[self.index(pos)]
+    }
+
+    /// The squares the search went through from the nearest source to `pos`. After a reverse
+    /// search, that is the climber's route from `pos` to the source, read backwards.
+    pub fn path_to(&self, pos: GridPos) -> Option<Path> {
+        self.distance(pos)?;
+
+        let mut path = vec![pos];
+        while let Some(parent) = self.parent(*path.last().unwrap()) {
+            path.push(parent);
+        }
+        path.reverse();
+
+        Some(path)
+    }
+}
+
+pub fn find_shortest_path(input: &str, start_pos: StartingPos) -> Option<u32> {
+    find_shortest_route(input, start_pos).map(|route| route.len() as u32 - 1)
+}
+
+/// The squares the climber walks through on a shortest route to `E`, both ends included.
+pub fn find_shortest_route(input: &str, start_pos: StartingPos) -> Option<Path> {
+    let board = parse_board(input);
+    let rules = MovementRules::default();
+
+    match start_pos {
+        StartingPos::S => board
+            .breadth_first(&[board.start], SearchDirection::Forward, &rules)
+            .path_to(board.end),
+        StartingPos::AllLowestPoints => {
+            // Searching backwards from `E` finds the distance from every lowest point at once.
+            let tree = board.breadth_first(&[board.end], SearchDirection::Reverse, &rules);
+            let nearest = board
+                .get_lowest_points()
+                .into_iter()
+                .filter(|&pos| tree.distance(pos).is_some())
+                .min_by_key(|&pos| tree.distance(pos))?;
+
+            let mut route = tree.path_to(nearest).unwrap();
+            route.reverse();
+            Some(route)
+        }
+    }
+}
+
+pub fn parse_board(input: &str) -> Board {
+    let mut start: GridPos = (0, 0);
+    let mut end: GridPos = (0, 0);
+    let mut grid: Vec<Vec<GridSquare>> = Vec::new();
+
+    for line in input.lines() {
+        let mut grid_line: Vec<GridSquare> = Vec::new();
+
+        for char in line.chars() {
+            let mut height = char as u32;
+
+            match char {
+                'S' => {
+                    start = {
+                        height = 'a' as u32;
+                        (grid.len() as u32, grid_line.len() as u32)
+                    }
+                }
+                'E' => {
+                    end = {
+                        height = 'z' as u32;
+                        (grid.len() as u32, grid_line.len() as u32)
+                    }
+                }
+                _ => (),
+            }
+
+            grid_line.push(GridSquare { height });
+        }
+
+        grid.push(grid_line)
+    }
+
+    Board { start, end, grid }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_board() {
+        let input = r"aSa
+bcm
+Edc";
+
+        let want = Board {
+            start: (0, 1),
+            end: (2, 0),
+            grid: vec![
+                vec![
+                    // (0, 0): a
+                    GridSquare { height: 'a' as u32 },
+                    // (0, 1): S
+                    GridSquare { height: 'a' as u32 },
+                    // (0, 2): a
+                    GridSquare { height: 'a' as u32 },
+                ],
+                vec![
+                    // (1, 0): b
+                    GridSquare { height: 'b' as u32 },
+                    // (1, 1): c
+                    GridSquare { height: 'c' as u32 },
+                    // (1, 2): m
+                    GridSquare { height: 'm' as u32 },
+                ],
+                vec![
+                    // (2, 0): E
+                    GridSquare { height: 'z' as u32 },
+                    // (2, 1): d
+                    GridSquare { height: 'd' as u32 },
+                    // (2, 2): c
+                    GridSquare { height: 'c' as u32 },
+                ],
+            ],
+        };
+
+        assert_eq!(parse_board(input), want);
+    }
+
+    #[test]
+    fn test_part1() {
+        let input = include_str!("../test.txt");
+
+        assert_eq!(find_shortest_path(input, StartingPos::S).unwrap(), 31);
+    }
+
+    #[test]
+    fn test_part2() {
+        let input = include_str!("../test.txt");
+
+        assert_eq!(
+            find_shortest_path(input, StartingPos::AllLowestPoints).unwrap(),
+            29
+        );
+    }
+
+    #[test]
+    fn test_shortest_routes_are_walkable() {
+        let input = include_str!("../test.txt");
+        let board = parse_board(input);
+
+        for (start_pos, steps) in [(StartingPos::S, 31), (StartingPos::AllLowestPoints, 29)] {
+            let route = find_shortest_route(input, start_pos).unwrap();
+
+            assert_eq!(steps + 1, route.len());
+            assert_eq!(board.end, *route.last().unwrap());
+            assert_eq!('a' as u32, board.get_square_at(&route[0]).unwrap().height);
+            for step in route.windows(2) {
+                let (from, to) = (step[0], step[1]);
+                assert_eq!(1, from.0.abs_diff(to.0) + from.1.abs_diff(to.1));
+                let (from, to) = (
+                    board.get_square_at(&from).unwrap(),
+                    board.get_square_at(&to).unwrap(),
+                );
+                assert!(MovementRules::default().allows(from.height, to.height));
+            }
+        }
+    }
+
+    #[test]
+    fn test_unreachable_end() {
+        assert_eq!(None, find_shortest_path("SacE", StartingPos::S));
+        assert_eq!(
+            None,
+            find_shortest_path("SacE", StartingPos::AllLowestPoints)
+        );
+
+        let tree = parse_board("SacE").breadth_first(
+            &[(0, 0)],
+            SearchDirection::Forward,
+            &MovementRules::default(),
+        );
+        assert_eq!(Some(1), tree.distance((0, 1)));
+        assert_eq!(None, tree.distance((0, 2)));
+        assert_eq!(None, tree.path_to((0, 3)));
+    }
+
+    #[test]
+    fn test_reverse_search_matches_searching_from_every_lowest_point() {
+        // xorshift64, to get the same random boards every run.
+        let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+        let mut next = |bound: u64| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state % bound
+        };
+
+        for _ in 0..50 {
+            let rows: Vec<String> = (0..12)
+                .map(|_| (0..15).map(|_| (b'a' + next(6) as u8) as char).collect())
+                .collect();
+            let board = parse_board(&rows.join("\n"));
+            let end = (next(12) as u32, next(15) as u32);
+
+            let rules = MovementRules::default();
+            let reverse = board.breadth_first(&[end], SearchDirection::Reverse, &rules);
+            let fewest_steps = board
+                .get_lowest_points()
+                .into_iter()
+                .filter_map(|pos| reverse.distance(pos))
+                .min();
+
+            let brute_force = board
+                .get_lowest_points()
+                .into_iter()
+                .filter_map(|pos| {
+                    board
+                        .breadth_first(&[pos], SearchDirection::Forward, &rules)
+                        .distance(end)
+                })
+                .min();
+            assert_eq!(brute_force, fewest_steps);
+        }
+    }
+}
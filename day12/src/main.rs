@@ -1,374 +1,130 @@
-use std::collections::VecDeque;
-
-type GridPos = (u32, u32);
-type Path = Vec<GridPos>;
-type ShortestPath = Option<Path>;
-
-enum StartingPos {
-    S,
-    AllLowestPoints,
-}
-
-#[derive(Debug, PartialEq, Clone)]
-struct GridSquare {
-    height: u32,
-    visited: bool,
-}
+use std::fs::File;
+use std::io::BufWriter;
+
+use day12::{
+    find_shortest_path, parse_board, Algorithm, Background, EffortCost, MovementRules,
+    SearchDirection, StartingPos,
+};
+
+/// Prints both parts, or with any of the options below the fewest steps from `S` to `E` under
+/// the given movement rules, and with `--algorithm` or a cost option also the cheapest route:
+///
+/// `day12 [--algorithm dijkstra|astar] [--max-climb <n>|none] [--max-descent <n>|none] [--diagonal]
+/// [--step-cost <n>] [--climb-cost <n>] [--descent-cost <n>]`
+///
+/// Every move costs `--step-cost`, 1 by default, plus the climb or descent cost per level.
+///
+/// The shortest route can be drawn over dots or the heights, and the distances from `S` and the
+/// squares that cannot be reached from it exported as images:
+///
+/// `day12 [--render dots|heights] [--distance-pgm <file>] [--unreachable-pbm <file>]`
+fn main() {
+    let input = include_str!("../input.txt");
 
-impl GridSquare {
-    fn set_visited(&mut self) {
-        self.visited = true;
+    let mut args = std::env::args().skip(1).peekable();
+    if args.peek().is_none() {
+        println!(
+            "Result of part 1: {}",
+            find_shortest_path(input, StartingPos::S).unwrap()
+        );
+        println!(
+            "Result of part 2: {}",
+            find_shortest_path(input, StartingPos::AllLowestPoints).unwrap()
+        );
+        return;
     }
-}
-
-#[derive(Debug, PartialEq, Clone)]
-struct Board {
-    start: GridPos,
-    end: GridPos,
-    grid: Vec<Vec<GridSquare>>,
-}
 
-impl Board {
-    fn get_lowest_points(&self) -> Vec<GridPos> {
-        let mut lowest_points = Vec::new();
-
-        for (y, row) in self.grid.iter().enumerate() {
-            for (x, square) in row.iter().enumerate() {
-                if square.height == 'a' as u32 {
-                    lowest_points.push((y as u32, x as u32));
+    let mut algorithm = Algorithm::AStar;
+    let mut rules = MovementRules::default();
+    let mut move_cost = EffortCost {
+        step: 1,
+        per_level_climbed: 0,
+        per_level_descended: 0,
+    };
+    let mut weighted = false;
+    let mut background = None;
+    let mut distance_file = None;
+    let mut unreachable_file = None;
+    while let Some(arg) = args.next() {
+        weighted |= arg == "--algorithm" || arg.ends_with("-cost");
+        match arg.as_str() {
+            "--algorithm" => {
+                algorithm = match args.next().as_deref() {
+                    Some("dijkstra") => Algorithm::Dijkstra,
+                    Some("astar") => Algorithm::AStar,
+                    _ => panic!("--algorithm requires dijkstra or astar"),
+                }
+            }
+            "--max-climb" => rules.max_climb = parse_limit_arg(&arg, args.next()),
+            "--max-descent" => rules.max_descent = parse_limit_arg(&arg, args.next()),
+            "--diagonal" => rules.diagonal = true,
+            "--step-cost" => move_cost.step = parse_number_arg(&arg, args.next()),
+            "--climb-cost" => move_cost.per_level_climbed = parse_number_arg(&arg, args.next()),
+            "--descent-cost" => move_cost.per_level_descended = parse_number_arg(&arg, args.next()),
+            "--render" => {
+                background = match args.next().as_deref() {
+                    Some("dots") => Some(Background::Dots),
+                    Some("heights") => Some(Background::Heights),
+                    _ => panic!("--render requires dots or heights"),
                 }
             }
+            "--distance-pgm" => {
+                distance_file = Some(args.next().expect("--distance-pgm requires a file"))
+            }
+            "--unreachable-pbm" => {
+                unreachable_file = Some(args.next().expect("--unreachable-pbm requires a file"))
+            }
+            _ => panic!("Unknown argument {}", arg),
         }
-
-        lowest_points
     }
 
-    fn get_square_mut_at(&mut self, pos: GridPos) -> Option<&mut GridSquare> {
-        let (row, col) = pos;
-        self.grid
-            .get_mut(row as usize)
-            .and_then(|row| row.get_mut(col as usize))
+    let board = parse_board(input);
+    let tree = board.breadth_first(&[board.start], SearchDirection::Forward, &rules);
+    let route = tree.path_to(board.end);
+    match &route {
+        Some(route) => println!("Fewest steps: {}", route.len() - 1),
+        None => println!("No route from S to E"),
     }
-
-    fn get_square_at(&self, pos: &GridPos) -> Option<&GridSquare> {
-        let (row, col) = pos;
-        self.grid
-            .get(*row as usize)
-            .and_then(|row| row.get(*col as usize))
+    if let Some(background) = background {
+        print!(
+            "{}",
+            board.render_route(route.as_deref().unwrap_or_default(), background)
+        );
     }
-
-    fn is_reachable(&self, source: &GridSquare, target: &GridSquare) -> bool {
-        target.height <= source.height + 1
+    if let Some(path) = distance_file {
+        tree.write_distance_pgm(create_file(&path)).unwrap();
     }
-
-    fn add_direction_if_reachable_and_not_visited(
-        &self,
-        source_square: &GridSquare,
-        target_pos: GridPos,
-        directions: &mut Vec<GridPos>,
-    ) -> Result<(), String> {
-        self.get_square_at(&target_pos)
-            .ok_or_else(|| format!("Invalid position {:?}", target_pos))
-            .map(|target_square| {
-                if !target_square.visited && self.is_reachable(source_square, target_square) {
-                    directions.push(target_pos);
-                }
-            })
+    if let Some(path) = unreachable_file {
+        tree.write_unreachable_pbm(create_file(&path)).unwrap();
     }
 
-    fn get_reachable_unvisited_neighbours(&self, pos: GridPos) -> Result<Vec<GridPos>, String> {
-        let mut directions = Vec::new();
-        let mut target_pos: GridPos;
-        let source_square = self
-            .get_square_at(&pos)
-            .ok_or_else(|| format!("Invalid position {:?}", pos))?;
-        let (row, col) = pos;
-
-        // Top
-        if row > 0 && row < self.grid.len() as u32 {
-            target_pos = (row - 1, col);
-
-            self.add_direction_if_reachable_and_not_visited(
-                source_square,
-                target_pos,
-                &mut directions,
-            )?;
-        }
-
-        // Bottom
-        if row < self.grid.len() as u32 - 1 {
-            target_pos = (row + 1, col);
-
-            self.add_direction_if_reachable_and_not_visited(
-                source_square,
-                target_pos,
-                &mut directions,
-            )?;
-        }
-
-        // Left
-        if col > 0 && col < self.grid[0].len() as u32 {
-            target_pos = (row, col - 1);
-
-            self.add_direction_if_reachable_and_not_visited(
-                source_square,
-                target_pos,
-                &mut directions,
-            )?;
-        }
-
-        // Right
-        if col < self.grid[0].len() as u32 - 1 {
-            target_pos = (row, col + 1);
-
-            self.add_direction_if_reachable_and_not_visited(
-                source_square,
-                target_pos,
-                &mut directions,
-            )?;
+    if weighted {
+        match board.find_cheapest_route(board.start, board.end, &rules, &move_cost, algorithm) {
+            Some(route) => println!(
+                "Cheapest route: cost {} in {} steps, {} squares expanded",
+                route.cost,
+                route.path.len() - 1,
+                route.expanded
+            ),
+            None => println!("No route from S to E"),
         }
-
-        return Ok(directions);
     }
 }
 
-fn main() {
-    let input = include_str!("../input.txt");
-
-    println!(
-        "Result of part 1: {}",
-        find_shortest_path(input, StartingPos::S).unwrap()
-    );
-    println!(
-        "Result of part 2: {}",
-        find_shortest_path(input, StartingPos::AllLowestPoints).unwrap()
-    );
+fn create_file(path: &str) -> BufWriter<File> {
+    BufWriter::new(File::create(path).unwrap_or_else(|err| panic!("{}: {}", path, err)))
 }
 
-fn find_shortest_path(input: &str, start_pos: StartingPos) -> Option<u32> {
-    let mut board = parse_board(input);
-
-    match start_pos {
-        StartingPos::S => {
-            let start_pos = board.start;
-            follow_path_from(&mut board, start_pos)
-        }
-        StartingPos::AllLowestPoints => {
-            let lowest_starting_positions = board.get_lowest_points();
-            let mut shortest_path = u32::MAX;
-
-            for pos in lowest_starting_positions {
-                let mut fresh_board = board.clone();
-                let path_length = follow_path_from(&mut fresh_board, pos);
-
-                if path_length.is_some() && path_length.unwrap() < shortest_path {
-                    shortest_path = path_length.unwrap();
-                }
-            }
-
-            Some(shortest_path)
-        }
+/// A number of levels, or `none` for no limit.
+fn parse_limit_arg(option: &str, value: Option<String>) -> Option<u32> {
+    match value.as_deref() {
+        Some("none") => None,
+        _ => Some(parse_number_arg(option, value)),
     }
 }
 
-fn follow_path_from(mut board: &mut Board, start_pos: GridPos) -> Option<u32> {
-    let mut shortest_path: ShortestPath = None;
-
-    let mut possible_paths: VecDeque<Path> = VecDeque::new();
-    possible_paths.push_back(vec![start_pos]);
-
-    board.get_square_mut_at(start_pos).unwrap().set_visited();
-
-    shortest_path = breadth_first(&mut board, &mut possible_paths, shortest_path);
-
-    if shortest_path.is_none() {
-        return None;
-    }
-
-    return Some(shortest_path.unwrap().len() as u32 - 1);
-}
-
-fn breadth_first(
-    board: &mut Board,
-    possible_paths: &mut VecDeque<Path>,
-    mut shortest_path: ShortestPath,
-) -> ShortestPath {
-    for _ in 0..possible_paths.len() {
-        let current_path = possible_paths.pop_front().unwrap();
-
-        let neighbours = board
-            .get_reachable_unvisited_neighbours(*current_path.last().unwrap())
-            .unwrap();
-
-        if neighbours.is_empty() {
-            continue;
-        }
-
-        for n in neighbours {
-            let mut new_path = current_path.clone();
-
-            new_path.push(n);
-            board.get_square_mut_at(n).unwrap().set_visited();
-
-            if n == board.end {
-                println!("Reached end position: {:?}", board.end);
-                println!("Steps count: {:?}", new_path.len() - 1);
-
-                if shortest_path.is_none() {
-                    shortest_path = Some(new_path.clone());
-                } else {
-                    if new_path.len() < shortest_path.as_ref().unwrap().len() {
-                        println!(
-                            "New shortest path found with steps count {}.",
-                            new_path.len() - 1
-                        );
-                        shortest_path = Some(new_path.clone());
-                    } else {
-                        println!(
-                            "Path is longer than the currently shortest path found ({} steps). 
-                        Discarding it.",
-                            shortest_path.as_ref().unwrap().len() - 1
-                        );
-                        continue;
-                    }
-                }
-            }
-
-            possible_paths.push_back(new_path.clone());
-        }
-    }
-
-    if !possible_paths.is_empty() {
-        shortest_path = breadth_first(board, possible_paths, shortest_path);
-    }
-
-    return shortest_path;
-}
-
-fn parse_board(input: &str) -> Board {
-    let mut start: GridPos = (0, 0);
-    let mut end: GridPos = (0, 0);
-    let mut grid: Vec<Vec<GridSquare>> = Vec::new();
-
-    for line in input.lines() {
-        let mut grid_line: Vec<GridSquare> = Vec::new();
-
-        for char in line.chars() {
-            let mut height = char as u32;
-
-            match char {
-                'S' => {
-                    start = {
-                        height = 'a' as u32;
-                        (grid.len() as u32, grid_line.len() as u32)
-                    }
-                }
-                'E' => {
-                    end = {
-                        height = 'z' as u32;
-                        (grid.len() as u32, grid_line.len() as u32)
-                    }
-                }
-                _ => (),
-            }
-
-            grid_line.push(GridSquare {
-                height,
-                visited: false,
-            });
-        }
-
-        grid.push(grid_line)
-    }
-
-    return Board { start, end, grid };
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_parse_board() {
-        let input = r"aSa
-bcm
-Edc";
-
-        let want = Board {
-            start: (0, 1),
-            end: (2, 0),
-            grid: vec![
-                vec![
-                    // (0, 0): a
-                    GridSquare {
-                        height: 'a' as u32,
-                        visited: false,
-                    },
-                    // (0, 1): S
-                    GridSquare {
-                        height: 'a' as u32,
-                        visited: false,
-                    },
-                    // (0, 2): a
-                    GridSquare {
-                        height: 'a' as u32,
-                        visited: false,
-                    },
-                ],
-                vec![
-                    // (1, 0): b
-                    GridSquare {
-                        height: 'b' as u32,
-                        visited: false,
-                    },
-                    // (1, 1): c
-                    GridSquare {
-                        height: 'c' as u32,
-                        visited: false,
-                    },
-                    // (1, 2): m
-                    GridSquare {
-                        height: 'm' as u32,
-                        visited: false,
-                    },
-                ],
-                vec![
-                    // (2, 0): E
-                    GridSquare {
-                        height: 'z' as u32,
-                        visited: false,
-                    },
-                    // (2, 1): d
-                    GridSquare {
-                        height: 'd' as u32,
-                        visited: false,
-                    },
-                    // (2, 2): c
-                    GridSquare {
-                        height: 'c' as u32,
-                        visited: false,
-                    },
-                ],
-            ],
-        };
-
-        assert_eq!(parse_board(input), want);
-    }
-
-    #[test]
-    fn test_part1() {
-        let input = include_str!("../test.txt");
-
-        assert_eq!(find_shortest_path(input, StartingPos::S).unwrap(), 31);
-    }
-
-    #[test]
-    fn test_part2() {
-        let input = include_str!("../test.txt");
-
-        assert_eq!(
-            find_shortest_path(input, StartingPos::AllLowestPoints).unwrap(),
-            29
-        );
-    }
+fn parse_number_arg(option: &str, value: Option<String>) -> u32 {
+    value
+        .and_then(|value| value.parse().ok())
+        .unwrap_or_else(|| panic!("{} requires a number", option))
 }
@@ -0,0 +1,295 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crate::{Board, GridPos, Path};
+
+/// Which moves the climber can make.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MovementRules {
+    /// How many levels a single move can climb, `None` for no limit.
+    pub max_climb: Option<u32>,
+    /// How many levels a single move can descend, `None` for no limit.
+    pub max_descent: Option<u32>,
+    /// Whether the climber can also move to the four diagonal neighbours.
+    pub diagonal: bool,
+}
+
+impl Default for MovementRules {
+    /// The puzzle's rules: climbing at most one level, descending any number of levels, and
+    /// moving up, down, left and right only.
+    fn default() -> Self {
+        MovementRules {
+            max_climb: Some(1),
+            max_descent: None,
+            diagonal: false,
+        }
+    }
+}
+
+impl MovementRules {
+    pub fn allows(&self, from_height: u32, to_height: u32) -> bool {
+        let climb = to_height.saturating_sub(from_height);
+        let descent = from_height.saturating_sub(to_height);
+        self.max_climb.is_none_or(|max_climb| climb <= max_climb)
+            && self
+                .max_descent
+                .is_none_or(|max_descent| descent <= max_descent)
+    }
+}
+
+/// What a single move costs, depending on how far it climbs (positive) or descends (negative).
+pub trait MoveCost {
+    fn cost(&self, height_difference: i32) -> u32;
+
+    /// A lower bound of every move's cost, which keeps the A* heuristic from overestimating.
+    fn min_cost(&self) -> u32;
+}
+
+/// Every move costs 1, so the cheapest route is the one with the fewest steps.
+pub struct UnitCost;
+
+impl MoveCost for UnitCost {
+    fn cost(&self, _height_difference: i32) -> u32 {
+        1
+    }
+
+    fn min_cost(&self) -> u32 {
+        1
+    }
+}
+
+/// A base cost per step, plus a cost for every level climbed or descended.
+pub struct EffortCost {
+    pub step: u32,
+    pub per_level_climbed: u32,
+    pub per_level_descended: u32,
+}
+
+impl MoveCost for EffortCost {
+    fn cost(&self, height_difference: i32) -> u32 {
+        let levels = height_difference.unsigned_abs();
+        let per_level = if height_difference > 0 {
+            self.per_level_climbed
+        } else {
+            self.per_level_descended
+        };
+        self.step + levels * per_level
+    }
+
+    fn min_cost(&self) -> u32 {
+        self.step
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Algorithm {
+    Dijkstra,
+    /// Dijkstra guided by the Manhattan distance to the goal, or the Chebyshev distance when
+    /// diagonal moves are allowed, times the cheapest possible move.
+    AStar,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Route {
+    /// From the start to the goal, both included.
+    pub path: Path,
+    pub cost: u32,
+    /// How many squares the search settled before reaching the goal.
+    pub expanded: usize,
+}
+
+impl Board {
+    /// The squares the climber can move to from `pos`, following `rules`.
+    pub fn moves<'a>(
+        &'a self,
+        pos: GridPos,
+        rules: &'a MovementRules,
+    ) -> impl Iterator<Item = GridPos> + 'a {
+        let height = self.get_square_at(&pos).unwrap().height;
+        self.neighbours(pos, rules.diagonal)
+            .filter(move |next| rules.allows(height, self.get_square_at(next).unwrap().height))
+    }
+
+    /// The cheapest route from `start` to `goal`, `None` if the rules do not allow any.
+    pub fn find_cheapest_route(
+        &self,
+        start: GridPos,
+        goal: GridPos,
+        rules: &MovementRules,
+        move_cost: &dyn MoveCost,
+        algorithm: Algorithm,
+    ) -> Option<Route> {
+        let width = self.width();
+        let index = |(row, col): GridPos| (row * width + col) as usize;
+        let heuristic = |(row, col): GridPos| match algorithm {
+            Algorithm::Dijkstra => 0,
+            Algorithm::AStar => {
+                let (rows, cols) = (row.abs_diff(goal.0), col.abs_diff(goal.1));
+                let moves = if rules.diagonal {
+                    rows.max(cols)
+                } else {
+                    rows + cols
+                };
+                moves * move_cost.min_cost()
+            }
+        };
+
+        let size = (width * self.height()) as usize;
+        let mut costs: Vec<Option<u32>> = vec![None; size];
+        let mut parents: Vec<Option<GridPos>> = vec![None; size];
+        let mut settled = vec![false; size];
+        let mut expanded = 0;
+
+        // Ordered by the estimated total cost. On ties, the square that got further comes first,
+        // which keeps A* from widening the search along the goal's contour.
+        let mut queue = BinaryHeap::new();
+        costs[index(start)] = Some(0);
+        queue.push(Reverse((heuristic(start), Reverse(0), start)));
+
+        while let Some(Reverse((_, Reverse(cost), pos))) = queue.pop() {
+            if settled[index(pos)] {
+                continue;
+            }
+            settled[index(pos)] = true;
+            expanded += 1;
+
+            if pos == goal {
+                let mut path = vec![goal];
+                while let Some(parent) = parents[index(*path.last().unwrap())] {
+                    path.push(parent);
+                }
+                path.reverse();
+                return Some(Route {
+                    path,
+                    cost,
+                    expanded,
+                });
+            }
+
+            let height = self.get_square_at(&pos).unwrap().height as i32;
+            for next in self.moves(pos, rules) {
+                let next_height = self.get_square_at(&next).unwrap().height as i32;
+                let next_cost = cost + move_cost.cost(next_height - height);
+                if costs[index(next)].is_none_or(|known| next_cost < known) {
+                    costs[index(next)] = Some(next_cost);
+                    parents[index(next)] = Some(pos);
+                    queue.push(Reverse((
+                        next_cost + heuristic(next),
+                        Reverse(next_cost),
+                        next,
+                    )));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_board, SearchDirection};
+
+    fn route(
+        input: &str,
+        rules: MovementRules,
+        move_cost: &dyn MoveCost,
+        algorithm: Algorithm,
+    ) -> Option<Route> {
+        let board = parse_board(input);
+        board.find_cheapest_route(board.start, board.end, &rules, move_cost, algorithm)
+    }
+
+    #[test]
+    fn test_fewest_steps_match_bfs() {
+        let input = include_str!("../input.txt");
+        let board = parse_board(input);
+        let rules = MovementRules::default();
+        let fewest_steps = board
+            .breadth_first(&[board.start], SearchDirection::Forward, &rules)
+            .distance(board.end)
+            .unwrap();
+
+        for algorithm in [Algorithm::Dijkstra, Algorithm::AStar] {
+            let route = route(input, rules, &UnitCost, algorithm).unwrap();
+            assert_eq!(fewest_steps, route.cost);
+            assert_eq!(fewest_steps as usize + 1, route.path.len());
+        }
+    }
+
+    #[test]
+    fn test_a_star_expands_fewer_squares() {
+        let input = include_str!("../input.txt");
+        let dijkstra = route(
+            input,
+            MovementRules::default(),
+            &UnitCost,
+            Algorithm::Dijkstra,
+        );
+        let a_star = route(input, MovementRules::default(), &UnitCost, Algorithm::AStar);
+
+        assert!(a_star.unwrap().expanded < dijkstra.unwrap().expanded);
+    }
+
+    #[test]
+    fn test_effort_cost() {
+        let cost = EffortCost {
+            step: 1,
+            per_level_climbed: 3,
+            per_level_descended: 1,
+        };
+        assert_eq!(7, cost.cost(2));
+        assert_eq!(3, cost.cost(-2));
+        assert_eq!(1, cost.cost(0));
+
+        // Going around the hill is longer, but cheaper than climbing it and descending again.
+        let input = "\
+Sbcba
+bzzzb
+aaaaE";
+        let climbing = EffortCost {
+            step: 1,
+            per_level_climbed: 10,
+            per_level_descended: 0,
+        };
+        let rules = MovementRules {
+            max_climb: None,
+            ..MovementRules::default()
+        };
+        for algorithm in [Algorithm::Dijkstra, Algorithm::AStar] {
+            let route = route(input, rules, &climbing, algorithm).unwrap();
+            // Down to the row of a's, along it and up to E: 6 steps with 1 + 25 levels climbed,
+            // where the route over the top climbs 1 + 1 + 1 + 24 levels.
+            assert_eq!(vec![(0, 0), (1, 0), (2, 0)], route.path[..3]);
+            assert_eq!(6 + 10 * 26, route.cost);
+        }
+    }
+
+    #[test]
+    fn test_movement_limits_and_diagonals() {
+        let input = "\
+Sd
+bE";
+        let steep = MovementRules {
+            max_climb: Some(2),
+            ..MovementRules::default()
+        };
+        assert!(steep.allows(1, 3));
+        assert!(!steep.allows(1, 4));
+        assert_eq!(None, route(input, steep, &UnitCost, Algorithm::AStar));
+
+        let no_limits = MovementRules {
+            max_climb: None,
+            max_descent: Some(0),
+            diagonal: true,
+        };
+        let route = route(input, no_limits, &UnitCost, Algorithm::AStar).unwrap();
+        assert_eq!(vec![(0, 0), (1, 1)], route.path);
+
+        // Without descents, nothing can be reached from the top of the board.
+        let board = parse_board(input);
+        let tree = board.breadth_first(&[board.end], SearchDirection::Forward, &no_limits);
+        assert_eq!(None, tree.distance((0, 1)));
+    }
+}
@@ -0,0 +1,177 @@
+use std::cmp::Ordering;
+use std::io::{self, Write};
+
+use crate::{Board, GridPos, SearchTree};
+
+/// What the squares off the route are drawn as.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Background {
+    /// `.`, like the puzzle text.
+    Dots,
+    /// Their height, `a` to `z`.
+    Heights,
+}
+
+impl Board {
+    /// Draws the board with every square of `route` showing the direction of the next step,
+    /// `^`, `v`, `<` or `>`, and `E` at the end of the route. Diagonal steps are drawn as `\`
+    /// or `/`.
+    pub fn render_route(&self, route: &[GridPos], background: Background) -> String {
+        let mut lines: Vec<Vec<char>> = self
+            .grid
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|square| match background {
+                        Background::Dots => '.',
+                        Background::Heights => char::from_u32(square.height).unwrap(),
+                    })
+                    .collect()
+            })
+            .collect();
+
+        for step in route.windows(2) {
+            let ((row, col), (next_row, next_col)) = (step[0], step[1]);
+            lines[row as usize][col as usize] = match (next_row.cmp(&row), next_col.cmp(&col)) {
+                (Ordering::Less, Ordering::Equal) => '^',
+                (Ordering::Greater, Ordering::Equal) => 'v',
+                (Ordering::Equal, Ordering::Less) => '<',
+                (Ordering::Equal, Ordering::Greater) => '>',
+                (Ordering::Less, Ordering::Less) | (Ordering::Greater, Ordering::Greater) => '\\',
+                _ => '/',
+            };
+        }
+        if let Some(&(row, col)) = route.last() {
+            lines[row as usize][col as usize] = 'E';
+        }
+
+        let mut output = String::with_capacity((self.width() as usize + 1) * lines.len());
+        for line in lines {
+            output.extend(line);
+            output.push('\n');
+        }
+        output
+    }
+}
+
+impl SearchTree {
+    fn height(&self) -> u32 {
+        (self.distances.len() as u32)
+            .checked_div(self.width)
+            .unwrap_or(0)
+    }
+
+    /// Binary PGM (P5) of the distances from the nearest source. Sources are dark gray, the
+    /// farthest squares white and squares the search never reached black.
+    pub fn write_distance_pgm(&self, mut out: impl Write) -> io::Result<()> {
+        let max_distance = self.distances.iter().flatten().max().copied().unwrap_or(0);
+        let pixels: Vec<u8> = self
+            .distances
+            .iter()
+            .map(|distance| match distance {
+                Some(distance) => {
+                    64 + (191 * u64::from(*distance) / u64::from(max_distance.max(1))) as u8
+                }
+                None => 0,
+            })
+            .collect();
+
+        write!(out, "P5\n{} {}\n255\n", self.width, self.height())?;
+        out.write_all(&pixels)
+    }
+
+    /// Binary PBM (P4) with the squares the search never reached black.
+    pub fn write_unreachable_pbm(&self, mut out: impl Write) -> io::Result<()> {
+        let width = self.width as usize;
+        write!(out, "P4\n{} {}\n", width, self.height())?;
+
+        let row_bytes = width.div_ceil(8);
+        let mut bits = vec![0u8; row_bytes * self.height() as usize];
+        for (idx, distance) in self.distances.iter().enumerate() {
+            let (x, y) = (idx % width, idx / width);
+            if distance.is_none() {
+                bits[y * row_bytes + x / 8] |= 0x80 >> (x % 8);
+            }
+        }
+
+        out.write_all(&bits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{find_shortest_route, parse_board, MovementRules, SearchDirection, StartingPos};
+
+    #[test]
+    fn test_render_route() {
+        let input = include_str!("../test.txt");
+        let board = parse_board(input);
+        let route = find_shortest_route(input, StartingPos::S).unwrap();
+
+        // As long as the puzzle's route, which only takes the detour through the third column
+        // one row later.
+        let want = "\
+v..v<<<<
+>v.vv<<^
+.v.v>E^^
+.>v>>>^^
+..>>>>>^
+";
+        assert_eq!(want, board.render_route(&route, Background::Dots));
+
+        let want = "\
+vabv<<<<
+>vcvv<<^
+avcv>E^^
+a>v>>>^^
+ab>>>>>^
+";
+        assert_eq!(want, board.render_route(&route, Background::Heights));
+    }
+
+    #[test]
+    fn test_render_diagonal_route() {
+        let board = parse_board("Sab\nabc\nbcE");
+        let rendering = board.render_route(&[(0, 0), (1, 1), (0, 2), (1, 2)], Background::Dots);
+
+        assert_eq!("\\.v\n./E\n...\n", rendering);
+    }
+
+    #[test]
+    fn test_write_distance_pgm_and_unreachable_pbm() {
+        let board = parse_board("SacE");
+        let tree = board.breadth_first(
+            &[board.start],
+            SearchDirection::Forward,
+            &MovementRules::default(),
+        );
+
+        let mut pgm = Vec::new();
+        tree.write_distance_pgm(&mut pgm).unwrap();
+        let mut want = b"P5\n4 1\n255\n".to_vec();
+        want.extend_from_slice(&[64, 255, 0, 0]);
+        assert_eq!(want, pgm);
+
+        let mut pbm = Vec::new();
+        tree.write_unreachable_pbm(&mut pbm).unwrap();
+        let mut want = b"P4\n4 1\n".to_vec();
+        want.push(0b0011_0000);
+        assert_eq!(want, pbm);
+    }
+
+    #[test]
+    fn test_write_distance_pgm_with_long_distances() {
+        let tree = SearchTree {
+            width: 3,
+            distances: vec![Some(0), Some(u32::MAX / 2), Some(u32::MAX)],
+            parents: vec![None; 3],
+        };
+
+        let mut pgm = Vec::new();
+        tree.write_distance_pgm(&mut pgm).unwrap();
+        let mut want = b"P5\n3 1\n255\n".to_vec();
+        want.extend_from_slice(&[64, 159, 255]);
+        assert_eq!(want, pgm);
+    }
+}
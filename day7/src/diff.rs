@@ -0,0 +1,248 @@
+use std::fmt;
+
+use crate::{FSDir, FSNode};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Resized,
+}
+
+/// A single difference between two snapshots. Added and removed directories are reported as a
+/// whole, their contents are not listed separately.
+#[derive(Debug, PartialEq, Clone)]
+pub struct FSChange {
+    pub kind: ChangeKind,
+    pub path: String,
+    pub is_dir: bool,
+    pub old_size: u64,
+    pub new_size: u64,
+}
+
+impl FSChange {
+    pub fn size_delta(&self) -> i64 {
+        self.new_size as i64 - self.old_size as i64
+    }
+}
+
+impl fmt::Display for FSChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let marker = match self.kind {
+            ChangeKind::Added => '+',
+            ChangeKind::Removed => '-',
+            ChangeKind::Resized => '~',
+        };
+        let node_type = if self.is_dir { "dir" } else { "file" };
+        write!(
+            f,
+            "{} {} {} ({} -> {}, {:+})",
+            marker,
+            node_type,
+            self.path,
+            self.old_size,
+            self.new_size,
+            self.size_delta()
+        )
+    }
+}
+
+/// Compares two filesystem trees node by node, matching children by name and type. The names of
+/// the roots are ignored so that scans of different locations can be compared.
+pub fn diff_fstrees(old: &FSDir, new: &FSDir) -> Vec<FSChange> {
+    let mut changes = Vec::new();
+    diff_dirs(old, new, "", &mut changes);
+    changes
+}
+
+fn diff_dirs(old: &FSDir, new: &FSDir, path: &str, changes: &mut Vec<FSChange>) {
+    if old.size != new.size {
+        changes.push(FSChange {
+            kind: ChangeKind::Resized,
+            path: display_path(path),
+            is_dir: true,
+            old_size: old.size,
+            new_size: new.size,
+        });
+    }
+
+    for old_child in old.children.iter() {
+        let child_path = format!("{}/{}", path, node_name(old_child));
+        match (old_child, find_counterpart(new, old_child)) {
+            (FSNode::Directory(old_dir), Some(FSNode::Directory(new_dir))) => {
+                diff_dirs(old_dir, new_dir, &child_path, changes)
+            }
+            (FSNode::File(old_file), Some(FSNode::File(new_file))) => {
+                if old_file.size != new_file.size {
+                    changes.push(FSChange {
+                        kind: ChangeKind::Resized,
+                        path: child_path,
+                        is_dir: false,
+                        old_size: old_file.size,
+                        new_size: new_file.size,
+                    });
+                }
+            }
+            _ => changes.push(FSChange {
+                kind: ChangeKind::Removed,
+                path: child_path,
+                is_dir: matches!(old_child, FSNode::Directory(..)),
+                old_size: node_size(old_child),
+                new_size: 0,
+            }),
+        }
+    }
+
+    for new_child in new.children.iter() {
+        if find_counterpart(old, new_child).is_none() {
+            changes.push(FSChange {
+                kind: ChangeKind::Added,
+                path: format!("{}/{}", path, node_name(new_child)),
+                is_dir: matches!(new_child, FSNode::Directory(..)),
+                old_size: 0,
+                new_size: node_size(new_child),
+            });
+        }
+    }
+}
+
+fn find_counterpart<'a>(dir: &'a FSDir, node: &FSNode) -> Option<&'a FSNode> {
+    dir.children
+        .iter()
+        .find(|candidate| match (candidate, node) {
+            (FSNode::File(a), FSNode::File(b)) => a.name == b.name,
+            (FSNode::Directory(a), FSNode::Directory(b)) => a.name == b.name,
+            _ => false,
+        })
+}
+
+fn node_name(node: &FSNode) -> &str {
+    match node {
+        FSNode::File(file) => &file.name,
+        FSNode::Directory(dir) => &dir.name,
+    }
+}
+
+fn node_size(node: &FSNode) -> u64 {
+    match node {
+        FSNode::File(file) => file.size,
+        FSNode::Directory(dir) => dir.size,
+    }
+}
+
+fn display_path(path: &str) -> String {
+    if path.is_empty() {
+        String::from("/")
+    } else {
+        path.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_fstree_from_cli_output;
+
+    #[test]
+    fn test_diff_identical_fstrees() {
+        let fstree = parse_fstree_from_cli_output(include_str!("../test.txt"));
+
+        assert_eq!(Vec::<FSChange>::new(), diff_fstrees(&fstree, &fstree));
+    }
+
+    #[test]
+    fn test_diff_fstrees() {
+        let old = parse_fstree_from_cli_output(include_str!("../test.txt"));
+        let new = parse_fstree_from_cli_output(
+            r#"$ cd /
+$ ls
+dir a
+14848514 b.txt
+dir c.dat
+$ cd a
+$ ls
+dir e
+29116 f
+2557 g
+60000 h.lst
+$ cd e
+$ ls
+584 i
+100 new
+$ cd ..
+$ cd ..
+$ cd c.dat
+$ ls
+10 x
+"#,
+        );
+
+        let want = vec![
+            FSChange {
+                kind: ChangeKind::Resized,
+                path: "/".to_string(),
+                is_dir: true,
+                old_size: 48381165,
+                new_size: 14940881,
+            },
+            FSChange {
+                kind: ChangeKind::Resized,
+                path: "/a".to_string(),
+                is_dir: true,
+                old_size: 94853,
+                new_size: 92357,
+            },
+            FSChange {
+                kind: ChangeKind::Resized,
+                path: "/a/e".to_string(),
+                is_dir: true,
+                old_size: 584,
+                new_size: 684,
+            },
+            FSChange {
+                kind: ChangeKind::Added,
+                path: "/a/e/new".to_string(),
+                is_dir: false,
+                old_size: 0,
+                new_size: 100,
+            },
+            FSChange {
+                kind: ChangeKind::Resized,
+                path: "/a/h.lst".to_string(),
+                is_dir: false,
+                old_size: 62596,
+                new_size: 60000,
+            },
+            FSChange {
+                kind: ChangeKind::Removed,
+                path: "/c.dat".to_string(),
+                is_dir: false,
+                old_size: 8504156,
+                new_size: 0,
+            },
+            FSChange {
+                kind: ChangeKind::Removed,
+                path: "/d".to_string(),
+                is_dir: true,
+                old_size: 24933642,
+                new_size: 0,
+            },
+            FSChange {
+                kind: ChangeKind::Added,
+                path: "/c.dat".to_string(),
+                is_dir: true,
+                old_size: 0,
+                new_size: 10,
+            },
+        ];
+
+        let got = diff_fstrees(&old, &new);
+
+        assert_eq!(want, got);
+        assert_eq!(-2496, got[1].size_delta());
+        assert_eq!(
+            "~ file /a/h.lst (62596 -> 60000, -2596)",
+            got[4].to_string()
+        );
+    }
+}
@@ -0,0 +1,396 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::fs::{self, Metadata};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::{FSDir, FSFile, FSNode};
+
+/// Controls how a directory on disk is walked into an `FSDir` tree.
+#[derive(Debug, Default, Clone)]
+pub struct ScanOptions {
+    /// Descend into symlinked directories and count symlinked files. Without this, symlinks are
+    /// skipped entirely. Directories inside the scanned tree are only counted where they are,
+    /// never via a link.
+    pub follow_symlinks: bool,
+    /// Do not cross into directories that live on a different device than the scan root
+    /// (like `du -x`). Only has an effect on Unix.
+    pub one_file_system: bool,
+    /// Glob patterns of entries to skip. A pattern is matched against the entry name and against
+    /// its path relative to the scan root. `*` and `?` do not match `/`, `**` does.
+    pub exclude: Vec<String>,
+}
+
+/// An entry that could not be read and is missing from the scanned tree.
+#[derive(Debug)]
+pub struct ScanError {
+    pub path: PathBuf,
+    pub error: io::Error,
+}
+
+impl fmt::Display for ScanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.error)
+    }
+}
+
+struct Scanner<'a> {
+    options: &'a ScanOptions,
+    root_device: Option<u64>,
+    root: PathBuf,
+    // Canonical paths of all directories outside the scan root expanded so far. Guards against
+    // symlink loops and against counting the same directory twice when it is reachable via
+    // several links.
+    visited_outside_dirs: HashSet<PathBuf>,
+    errors: Vec<ScanError>,
+}
+
+/// Walks the directory `root` and builds the same tree `parse_fstree_from_cli_output` builds from
+/// a terminal transcript. Children are sorted by name so that scans are reproducible.
+///
+/// Only fails if `root` itself cannot be read. Entries below it that cannot be read, like
+/// directories without permission or symlink loops, are left out of the tree and returned
+/// alongside it.
+pub fn scan_fstree_from_disk(
+    root: &Path,
+    options: &ScanOptions,
+) -> io::Result<(FSDir, Vec<ScanError>)> {
+    let root_meta = fs::metadata(root)?;
+    if !root_meta.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is not a directory", root.display()),
+        ));
+    }
+
+    let mut scanner = Scanner {
+        options,
+        root_device: device_id(&root_meta),
+        root: fs::canonicalize(root)?,
+        visited_outside_dirs: HashSet::new(),
+        errors: Vec::new(),
+    };
+
+    let mut fstree = FSDir::new(&root.display().to_string());
+    scanner.expand_dir(&mut fstree, root, Path::new(""));
+
+    Ok((fstree, scanner.errors))
+}
+
+impl Scanner<'_> {
+    fn expand_dir(&mut self, dir: &mut FSDir, path: &Path, rel_path: &Path) {
+        let read_entries = fs::read_dir(path).and_then(|entries| entries.collect());
+        let mut entries: Vec<fs::DirEntry> = match read_entries {
+            Ok(entries) => entries,
+            Err(error) => return self.skip(path, error),
+        };
+        entries.sort_by_key(|entry| entry.file_name());
+
+        for entry in entries {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let entry_rel_path = rel_path.join(&name);
+            if self.is_excluded(&name, &entry_rel_path) {
+                continue;
+            }
+
+            let entry_path = entry.path();
+            let (meta, is_symlink) = match self.metadata(&entry_path) {
+                Ok(Some(meta)) => meta,
+                Ok(None) => continue,
+                Err(error) => {
+                    self.skip(&entry_path, error);
+                    continue;
+                }
+            };
+
+            if meta.is_dir() {
+                if self.options.one_file_system && device_id(&meta) != self.root_device {
+                    continue;
+                }
+                match self.visit_dir(&entry_path, is_symlink) {
+                    Ok(true) => {}
+                    Ok(false) => continue,
+                    Err(error) => {
+                        self.skip(&entry_path, error);
+                        continue;
+                    }
+                }
+
+                let mut child = FSDir::new(&name);
+                self.expand_dir(&mut child, &entry_path, &entry_rel_path);
+                dir.size += child.size;
+                dir.children.push(FSNode::Directory(child));
+            } else if meta.is_file() {
+                dir.size += meta.len();
+                dir.children.push(FSNode::File(FSFile {
+                    name,
+                    size: meta.len(),
+                }));
+            }
+        }
+    }
+
+    fn skip(&mut self, path: &Path, error: io::Error) {
+        self.errors.push(ScanError {
+            path: path.to_path_buf(),
+            error,
+        });
+    }
+
+    /// Returns the metadata of the entry, or of its target if it is a symlink, and whether it is
+    /// a symlink. Returns `None` for entries that shall not be part of the tree: symlinks when
+    /// they are not followed and dangling symlinks when they are.
+    fn metadata(&self, path: &Path) -> io::Result<Option<(Metadata, bool)>> {
+        let meta = fs::symlink_metadata(path)?;
+        if !meta.file_type().is_symlink() {
+            return Ok(Some((meta, false)));
+        }
+        if !self.options.follow_symlinks {
+            return Ok(None);
+        }
+
+        match fs::metadata(path) {
+            Ok(meta) => Ok(Some((meta, true))),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Whether the directory at `path` shall be expanded. Directories inside the scan root are
+    /// expanded where they actually are, whatever order the entries are walked in, so links
+    /// into the root are skipped. Directories outside the root are only reachable via links and
+    /// expanded the first time they are reached.
+    fn visit_dir(&mut self, path: &Path, is_symlink: bool) -> io::Result<bool> {
+        let target = fs::canonicalize(path)?;
+        if target.starts_with(&self.root) {
+            return Ok(!is_symlink);
+        }
+        Ok(self.visited_outside_dirs.insert(target))
+    }
+
+    fn is_excluded(&self, name: &str, rel_path: &Path) -> bool {
+        let rel_path = rel_path
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        self.options
+            .exclude
+            .iter()
+            .any(|pattern| glob_matches(pattern, name) || glob_matches(pattern, &rel_path))
+    }
+}
+
+#[cfg(unix)]
+fn device_id(meta: &Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(meta.dev())
+}
+
+#[cfg(not(unix))]
+fn device_id(_meta: &Metadata) -> Option<u64> {
+    None
+}
+
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    glob_matches_from(&pattern, &text)
+}
+
+fn glob_matches_from(pattern: &[char], text: &[char]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        ['*', '*', rest @ ..] => {
+            (0..=text.len()).any(|skip| glob_matches_from(rest, &text[skip..]))
+        }
+        ['*', rest @ ..] => {
+            let max_skip = text.iter().position(|c| *c == '/').unwrap_or(text.len());
+            (0..=max_skip).any(|skip| glob_matches_from(rest, &text[skip..]))
+        }
+        ['?', rest @ ..] => match text {
+            [c, text_rest @ ..] if *c != '/' => glob_matches_from(rest, text_rest),
+            _ => false,
+        },
+        [p, rest @ ..] => match text {
+            [c, text_rest @ ..] if c == p => glob_matches_from(rest, text_rest),
+            _ => false,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_dir(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("day7-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("a/e")).unwrap();
+        fs::create_dir_all(root.join("d")).unwrap();
+        fs::write(root.join("b.txt"), vec![0; 100]).unwrap();
+        fs::write(root.join("a/f"), vec![0; 20]).unwrap();
+        fs::write(root.join("a/e/i"), vec![0; 3]).unwrap();
+        fs::write(root.join("d/d.log"), vec![0; 50]).unwrap();
+        root
+    }
+
+    #[test]
+    fn test_glob_matches() {
+        assert!(glob_matches("*.log", "d.log"));
+        assert!(!glob_matches("*.log", "d/d.log"));
+        assert!(glob_matches("**/*.log", "d/d.log"));
+        assert!(glob_matches("a/?", "a/e"));
+        assert!(!glob_matches("a?e", "a/e"));
+        assert!(glob_matches("target", "target"));
+    }
+
+    #[test]
+    fn test_scan_fstree_from_disk() {
+        let root = create_test_dir("scan");
+
+        let (got, errors) = scan_fstree_from_disk(&root, &ScanOptions::default()).unwrap();
+        assert!(errors.is_empty(), "{:?}", errors);
+
+        let want = FSDir {
+            name: root.display().to_string(),
+            children: vec![
+                FSNode::Directory(FSDir {
+                    name: "a".to_string(),
+                    children: vec![
+                        FSNode::Directory(FSDir {
+                            name: "e".to_string(),
+                            children: vec![FSNode::File(FSFile {
+                                name: "i".to_string(),
+                                size: 3,
+                            })],
+                            size: 3,
+                        }),
+                        FSNode::File(FSFile {
+                            name: "f".to_string(),
+                            size: 20,
+                        }),
+                    ],
+                    size: 23,
+                }),
+                FSNode::File(FSFile {
+                    name: "b.txt".to_string(),
+                    size: 100,
+                }),
+                FSNode::Directory(FSDir {
+                    name: "d".to_string(),
+                    children: vec![FSNode::File(FSFile {
+                        name: "d.log".to_string(),
+                        size: 50,
+                    })],
+                    size: 50,
+                }),
+            ],
+            size: 173,
+        };
+        assert_eq!(want, got);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_scan_fstree_from_disk_with_excludes() {
+        let root = create_test_dir("exclude");
+        let options = ScanOptions {
+            exclude: vec!["*.log".to_string(), "a/e".to_string()],
+            ..Default::default()
+        };
+
+        let got = scan_fstree_from_disk(&root, &options).unwrap().0;
+
+        assert_eq!(120, got.size);
+        assert_eq!(20, aggregate_size_of(&got, "a"));
+        assert_eq!(0, aggregate_size_of(&got, "d"));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_scan_fstree_from_disk_with_symlinks() {
+        let root = create_test_dir("symlinks");
+        std::os::unix::fs::symlink(root.join("d"), root.join("a/link_to_d")).unwrap();
+        std::os::unix::fs::symlink(&root, root.join("a/e/link_to_root")).unwrap();
+
+        let not_followed = scan_fstree_from_disk(&root, &ScanOptions::default())
+            .unwrap()
+            .0;
+        assert_eq!(173, not_followed.size);
+
+        let options = ScanOptions {
+            follow_symlinks: true,
+            ..Default::default()
+        };
+        let followed = scan_fstree_from_disk(&root, &options).unwrap().0;
+        // Links into the scan root are not followed, so neither the loop back to the root nor
+        // the second path to `d` inflate the total size. `a/link_to_d` comes first, but `d` is
+        // still listed where it actually is.
+        assert_eq!(173, followed.size);
+        assert_eq!(23, aggregate_size_of(&followed, "a"));
+        assert_eq!(50, aggregate_size_of(&followed, "d"));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_scan_fstree_from_disk_with_symlinks_out_of_the_root() {
+        let root = create_test_dir("outside");
+        let outside = create_test_dir("outside-target");
+        std::os::unix::fs::symlink(&outside, root.join("a/link_to_outside")).unwrap();
+        std::os::unix::fs::symlink(outside.join("d"), root.join("link_to_outside_d")).unwrap();
+        std::os::unix::fs::symlink(&outside, outside.join("d/link_back")).unwrap();
+
+        let options = ScanOptions {
+            follow_symlinks: true,
+            ..Default::default()
+        };
+        let got = scan_fstree_from_disk(&root, &options).unwrap().0;
+        // The outside tree is counted once via `a/link_to_outside`, `link_to_outside_d` leads into
+        // it a second time and `link_back` loops back to it.
+        assert_eq!(173 + 173, got.size);
+        assert_eq!(23 + 173, aggregate_size_of(&got, "a"));
+
+        fs::remove_dir_all(&root).unwrap();
+        fs::remove_dir_all(&outside).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_scan_fstree_from_disk_skips_unreadable_entries() {
+        let root = create_test_dir("unreadable");
+        let self_link = root.join("a/self_link");
+        std::os::unix::fs::symlink(&self_link, &self_link).unwrap();
+
+        let options = ScanOptions {
+            follow_symlinks: true,
+            ..Default::default()
+        };
+        let (got, errors) = scan_fstree_from_disk(&root, &options).unwrap();
+        // The link cannot be resolved, the rest of the tree is still scanned.
+        assert_eq!(173, got.size);
+        assert_eq!(1, errors.len());
+        assert_eq!(self_link, errors[0].path);
+
+        assert!(scan_fstree_from_disk(&root.join("missing"), &options).is_err());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    fn aggregate_size_of(fstree: &FSDir, dir_name: &str) -> u64 {
+        fstree
+            .children
+            .iter()
+            .find_map(|node| match node {
+                FSNode::Directory(dir) if dir.name == dir_name => Some(dir.size),
+                _ => None,
+            })
+            .unwrap()
+    }
+}
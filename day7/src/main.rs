@@ -1,3 +1,13 @@
+use std::path::Path;
+
+mod diff;
+mod disk;
+mod transcript;
+
+use diff::diff_fstrees;
+use disk::{scan_fstree_from_disk, ScanOptions};
+use transcript::render_cli_output;
+
 enum Cmd {
     Exit,
     CdUp,
@@ -26,14 +36,14 @@ enum FSNode {
 #[derive(Debug, PartialEq, Clone)]
 struct FSFile {
     name: String,
-    size: u32,
+    size: u64,
 }
 
 #[derive(Debug, PartialEq, Clone)]
 struct FSDir {
     name: String,
     children: Vec<FSNode>,
-    size: u32,
+    size: u64,
 }
 
 impl<'a> FSDir {
@@ -74,7 +84,7 @@ impl<'a> FSDir {
                 }
 
                 file => {
-                    let fsize = file.parse::<u32>().unwrap();
+                    let fsize = file.parse::<u64>().unwrap();
                     let fname = output_parts.next().unwrap();
                     self.children.push(FSNode::File(FSFile {
                         name: String::from(fname),
@@ -104,34 +114,110 @@ fn get_command(cmd: &str) -> Cmd {
     }
 }
 
+/// Total and required free space of the device the filesystem tree lives on.
+#[derive(Debug, Clone, PartialEq)]
+struct DiskSpace {
+    total: u64,
+    required_free: u64,
+}
+
+impl Default for DiskSpace {
+    fn default() -> Self {
+        Self {
+            total: 70_000_000,
+            required_free: 30_000_000,
+        }
+    }
+}
+
+/// Without arguments, the puzzle input is analysed. Otherwise the first non-option argument is a
+/// directory on disk that is scanned or a transcript file that is parsed instead. `--compare`
+/// lists the differences to a second directory or transcript, `--print-transcript` prints the
+/// analysed tree as a terminal transcript:
+///
+/// `day7 [--follow-symlinks] [--one-file-system] [--exclude <glob>]... [--disk-size <bytes>]
+/// [--required-space <bytes>] [--compare <dir|file>] [--print-transcript] [<dir|file>]`
 fn main() {
-    let input = include_str!("../input.txt");
+    let mut args = std::env::args().skip(1);
+    let mut scan_root: Option<String> = None;
+    let mut compare_to: Option<String> = None;
+    let mut print_transcript = false;
+    let mut scan_options = ScanOptions::default();
+    let mut disk_space = DiskSpace::default();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--follow-symlinks" => scan_options.follow_symlinks = true,
+            "--one-file-system" => scan_options.one_file_system = true,
+            "--exclude" => scan_options
+                .exclude
+                .push(args.next().expect("--exclude requires a glob pattern")),
+            "--disk-size" => disk_space.total = parse_size_arg(&arg, args.next()),
+            "--required-space" => disk_space.required_free = parse_size_arg(&arg, args.next()),
+            "--compare" => compare_to = Some(args.next().expect("--compare requires a path")),
+            "--print-transcript" => print_transcript = true,
+            _ => scan_root = Some(arg),
+        }
+    }
+
+    let fstree = match scan_root {
+        Some(root) => load_fstree(&root, &scan_options),
+        None => parse_fstree_from_cli_output(include_str!("../input.txt")),
+    };
+
+    if print_transcript {
+        let transcript = render_cli_output(&fstree)
+            .unwrap_or_else(|err| panic!("Failed to print the transcript: {}", err));
+        print!("{}", transcript);
+    }
+    if let Some(other) = compare_to {
+        for change in diff_fstrees(&fstree, &load_fstree(&other, &scan_options)) {
+            println!("{}", change);
+        }
+    }
 
-    let fstree = parse_fstree_from_cli_output(input);
     let day1_result = calculate_day_1(&fstree);
-    let day2_result = calculate_day_2(&fstree);
+    let day2_result = calculate_day_2(&fstree, &disk_space);
 
     println!("Result of day 1: {}", day1_result);
     println!("Result of day 2: {}", day2_result);
 }
 
-fn calculate_day_1(fstree: &FSDir) -> u32 {
-    return aggregate_dir_size_with_max_size_of(fstree, 100_000);
+/// Scans `path` if it is a directory, otherwise parses it as a terminal transcript.
+fn load_fstree(path: &str, scan_options: &ScanOptions) -> FSDir {
+    let path = Path::new(path);
+    if path.is_dir() {
+        let (fstree, errors) = scan_fstree_from_disk(path, scan_options)
+            .unwrap_or_else(|err| panic!("Failed to scan {}: {}", path.display(), err));
+        for error in errors {
+            eprintln!("Skipped {}", error);
+        }
+        fstree
+    } else {
+        let input = std::fs::read_to_string(path)
+            .unwrap_or_else(|err| panic!("Failed to read {}: {}", path.display(), err));
+        parse_fstree_from_cli_output(&input)
+    }
+}
+
+fn parse_size_arg(option: &str, value: Option<String>) -> u64 {
+    value
+        .and_then(|value| value.replace('_', "").parse().ok())
+        .unwrap_or_else(|| panic!("{} requires a size in bytes", option))
+}
+
+fn calculate_day_1(fstree: &FSDir) -> u64 {
+    aggregate_dir_size_with_max_size_of(fstree, 100_000)
 }
 
-fn calculate_day_2(fstree: &FSDir) -> u32 {
-    let disk_size = 70_000_000;
-    let required_space = 30_000_000;
-    let used_space = fstree.size;
-    let space_to_be_freed: u32 = (((disk_size - required_space) as i32 - used_space as i32))
-        .abs()
-        .try_into()
-        .unwrap();
+fn calculate_day_2(fstree: &FSDir, disk_space: &DiskSpace) -> u64 {
+    let free_space = disk_space.total.saturating_sub(fstree.size);
+    let space_to_be_freed = disk_space.required_free.saturating_sub(free_space);
 
-    return get_size_of_dir_to_delete(fstree, space_to_be_freed);
+    get_size_of_dir_to_delete(fstree, space_to_be_freed)
 }
 
-fn aggregate_dir_size_with_max_size_of(fstree: &FSDir, max_size: u32) -> u32 {
+fn aggregate_dir_size_with_max_size_of(fstree: &FSDir, max_size: u64) -> u64 {
     let mut total_size = 0;
 
     if fstree.size <= max_size {
@@ -148,11 +234,11 @@ fn aggregate_dir_size_with_max_size_of(fstree: &FSDir, max_size: u32) -> u32 {
         }
     }
 
-    return total_size;
+    total_size
 }
 
-fn get_size_of_dir_to_delete(fstree: &FSDir, required_size: u32) -> u32 {
-    let mut smallest_sufficient_dir_size = u32::MAX;
+fn get_size_of_dir_to_delete(fstree: &FSDir, required_size: u64) -> u64 {
+    let mut smallest_sufficient_dir_size = u64::MAX;
 
     if fstree.size >= required_size {
         smallest_sufficient_dir_size = fstree.size;
@@ -170,7 +256,7 @@ fn get_size_of_dir_to_delete(fstree: &FSDir, required_size: u32) -> u32 {
         }
     }
 
-    return smallest_sufficient_dir_size;
+    smallest_sufficient_dir_size
 }
 
 fn parse_fstree_from_cli_output(input: &str) -> FSDir {
@@ -191,7 +277,7 @@ fn parse_fstree_from_cli_output(input: &str) -> FSDir {
 
     let result = consume_cli_output_cmds(cli_output_iterator, fstree);
 
-    return result.expanded_dir;
+    result.expanded_dir
 }
 
 fn consume_cli_output_cmds<'a>(
@@ -202,8 +288,8 @@ fn consume_cli_output_cmds<'a>(
     let mut next_cmd: Cmd;
 
     loop {
-        if !received_next_cmd.is_none() {
-            next_cmd = received_next_cmd.unwrap();
+        if let Some(cmd) = received_next_cmd.take() {
+            next_cmd = cmd;
         } else {
             let cli_output = cli_output_iterator
                 .next()
@@ -270,7 +356,7 @@ fn consume_cli_output_cmds<'a>(
 }
 
 fn is_command(first_cli_token: &str) -> bool {
-    return first_cli_token.chars().nth(0).unwrap() == '$' || first_cli_token == "exit";
+    first_cli_token.starts_with('$') || first_cli_token == "exit"
 }
 
 #[cfg(test)]
@@ -388,6 +474,18 @@ $ ls
     fn test_day2() {
         let fstree = parse_fstree_from_cli_output(include_str!("../test.txt"));
 
-        assert_eq!(24933642, calculate_day_2(&fstree));
+        assert_eq!(24933642, calculate_day_2(&fstree, &DiskSpace::default()));
+    }
+
+    #[test]
+    fn test_day2_with_custom_disk_space() {
+        let fstree = parse_fstree_from_cli_output(include_str!("../test.txt"));
+        let disk_space = DiskSpace {
+            total: 50_000_000,
+            required_free: 2_000_000,
+        };
+
+        // 48381165 used, 1618835 free: 381165 have to be freed, directory 'd' is the only one.
+        assert_eq!(24933642, calculate_day_2(&fstree, &disk_space));
     }
 }
@@ -0,0 +1,152 @@
+use std::fmt::{self, Write};
+
+use crate::{FSDir, FSNode};
+
+/// A name that cannot be written into a transcript without changing the tree it parses to.
+#[derive(Debug, PartialEq)]
+pub struct InvalidNameError {
+    pub name: String,
+}
+
+impl fmt::Display for InvalidNameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:?} cannot be part of a transcript, names must not be empty, \"..\" or contain whitespace",
+            self.name
+        )
+    }
+}
+
+/// Produces the terminal transcript of exploring `fstree`, the inverse of
+/// `parse_fstree_from_cli_output`: every directory is entered with `$ cd`, listed with `$ ls` and
+/// left again with `$ cd ..` once its subdirectories have been visited.
+///
+/// Fails on the first name that is empty, `..` or contains whitespace. Siblings must also have
+/// distinct names, otherwise the transcript is parsed back into a different tree.
+pub fn render_cli_output(fstree: &FSDir) -> Result<String, InvalidNameError> {
+    let mut output = String::new();
+    writeln!(output, "$ cd {}", checked_name(&fstree.name)?).unwrap();
+    render_dir(fstree, &mut output)?;
+    Ok(output)
+}
+
+fn render_dir(dir: &FSDir, output: &mut String) -> Result<(), InvalidNameError> {
+    writeln!(output, "$ ls").unwrap();
+    for child in dir.children.iter() {
+        match child {
+            FSNode::File(file) => {
+                writeln!(output, "{} {}", file.size, checked_name(&file.name)?).unwrap()
+            }
+            FSNode::Directory(subdir) => {
+                writeln!(output, "dir {}", checked_name(&subdir.name)?).unwrap()
+            }
+        }
+    }
+
+    for child in dir.children.iter() {
+        if let FSNode::Directory(subdir) = child {
+            writeln!(output, "$ cd {}", subdir.name).unwrap();
+            render_dir(subdir, output)?;
+            writeln!(output, "$ cd ..").unwrap();
+        }
+    }
+    Ok(())
+}
+
+fn checked_name(name: &str) -> Result<&str, InvalidNameError> {
+    if name.is_empty() || name == ".." || name.contains(char::is_whitespace) {
+        return Err(InvalidNameError {
+            name: name.to_string(),
+        });
+    }
+    Ok(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_fstree_from_cli_output, FSFile};
+
+    /// xorshift64, good enough to generate test trees without pulling in a crate.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self, bound: u64) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0 % bound
+        }
+    }
+
+    fn generate_dir(rng: &mut Rng, name: String, depth: u32) -> FSDir {
+        let mut dir = FSDir::new(&name);
+        let child_count = rng.next(6);
+        for idx in 0..child_count {
+            if depth > 0 && rng.next(3) == 0 {
+                let subdir = generate_dir(rng, format!("d{}", idx), depth - 1);
+                dir.size += subdir.size;
+                dir.children.push(FSNode::Directory(subdir));
+            } else {
+                let size = rng.next(1_000_000);
+                dir.size += size;
+                dir.children.push(FSNode::File(FSFile {
+                    name: format!("f{}.{}", idx, rng.next(100)),
+                    size,
+                }));
+            }
+        }
+        dir
+    }
+
+    #[test]
+    fn test_render_cli_output_of_puzzle_example() {
+        let input = include_str!("../test.txt");
+        let fstree = parse_fstree_from_cli_output(input);
+
+        let got = render_cli_output(&fstree).unwrap();
+
+        assert_eq!(fstree, parse_fstree_from_cli_output(&got));
+    }
+
+    #[test]
+    fn test_render_cli_output_round_trip() {
+        for seed in 1..=500u64 {
+            let mut rng = Rng(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+            let fstree = generate_dir(&mut rng, "/".to_string(), 4);
+
+            let transcript = render_cli_output(&fstree).unwrap();
+
+            assert_eq!(
+                fstree,
+                parse_fstree_from_cli_output(&transcript),
+                "round trip failed for seed {}:\n{}",
+                seed,
+                transcript
+            );
+        }
+    }
+
+    #[test]
+    fn test_render_cli_output_rejects_names_that_do_not_round_trip() {
+        for name in ["my file", "line\nbreak", "tab\t", "", ".."] {
+            let mut fstree = parse_fstree_from_cli_output(include_str!("../test.txt"));
+            fstree.children.push(FSNode::File(FSFile {
+                name: name.to_string(),
+                size: 1,
+            }));
+            let mut subdir = FSDir::new("sub");
+            subdir.children.push(FSNode::Directory(FSDir::new(name)));
+            fstree.children.push(FSNode::Directory(subdir));
+
+            let want = Err(InvalidNameError {
+                name: name.to_string(),
+            });
+            assert_eq!(want, render_cli_output(&fstree), "{:?}", name);
+            fstree.children.remove(fstree.children.len() - 2);
+            assert_eq!(want, render_cli_output(&fstree), "{:?}", name);
+            assert_eq!(want, render_cli_output(&FSDir::new(name)), "{:?}", name);
+        }
+    }
+}
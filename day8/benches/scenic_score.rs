@@ -0,0 +1,55 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use day8::{max_scenic_score, max_scenic_score_by_walking};
+
+/// A forest of random tree heights, generated with a xorshift so that every run sees the same
+/// forest.
+fn generate_forest(width: usize, height: usize) -> Vec<Vec<u8>> {
+    let mut state: u64 = 0x2545_F491_4F6C_DD1D;
+    (0..height)
+        .map(|_| {
+            (0..width)
+                .map(|_| {
+                    state ^= state << 13;
+                    state ^= state >> 7;
+                    state ^= state << 17;
+                    (state % 10) as u8
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Rows of trees that fall off from 255 to 0 over and over again. Every tree sees all the way down
+/// its run, which makes walking outward from every tree take ~128 steps per tree while the
+/// sweeps stay linear. On digit-only forests walks are bounded by the ten possible heights.
+fn generate_sawtooth(width: usize, height: usize) -> Vec<Vec<u8>> {
+    (0..height)
+        .map(|_| (0..width).map(|col| 255 - (col % 256) as u8).collect())
+        .collect()
+}
+
+pub fn criterion_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("scenic score");
+    group.sample_size(10);
+
+    let forest = generate_forest(5000, 5000);
+    group.bench_function("random 5000x5000 - monotonic stacks", |b| {
+        b.iter(|| max_scenic_score(black_box(&forest)));
+    });
+    group.bench_function("random 5000x5000 - walking", |b| {
+        b.iter(|| max_scenic_score_by_walking(black_box(&forest)));
+    });
+
+    let sawtooth = generate_sawtooth(5000, 5000);
+    group.bench_function("sawtooth 5000x5000 - monotonic stacks", |b| {
+        b.iter(|| max_scenic_score(black_box(&sawtooth)));
+    });
+    group.bench_function("sawtooth 5000x5000 - walking", |b| {
+        b.iter(|| max_scenic_score_by_walking(black_box(&sawtooth)));
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,232 @@
+use std::io::{self, Write};
+
+use crate::{calculate_tree_views, parse_tree_map, Direction, TreeMap, TreeView};
+
+/// Shades from lowest to highest for the ASCII heat maps.
+const ASCII_SHADES: &[u8] = b" .:-=+*#%@";
+
+/// A parsed forest together with the sightlines of all its trees, so that any tree can be
+/// inspected after a single O(w·h) pass. Coordinates are `(x, y)` with `x` being the column and
+/// `y` the row, starting at the top left.
+pub struct Forest {
+    tree_map: TreeMap,
+    views: Vec<Vec<TreeView>>,
+}
+
+impl Forest {
+    pub fn new(tree_map: TreeMap) -> Self {
+        let views = calculate_tree_views(&tree_map);
+        Self { tree_map, views }
+    }
+
+    pub fn parse(input: &str) -> Self {
+        Self::new(parse_tree_map(input))
+    }
+
+    pub fn width(&self) -> usize {
+        self.tree_map.first().map_or(0, |row| row.len())
+    }
+
+    pub fn height(&self) -> usize {
+        self.tree_map.len()
+    }
+
+    pub fn tree_size(&self, x: usize, y: usize) -> Option<u8> {
+        self.tree_map.get(y)?.get(x).copied()
+    }
+
+    /// The sightlines of the tree in all four directions.
+    pub fn view(&self, x: usize, y: usize) -> Option<&TreeView> {
+        self.views.get(y)?.get(x)
+    }
+
+    pub fn is_visible(&self, x: usize, y: usize) -> Option<bool> {
+        self.view(x, y).map(TreeView::is_visible)
+    }
+
+    pub fn visible_from(&self, x: usize, y: usize) -> Option<Vec<Direction>> {
+        self.view(x, y).map(TreeView::visible_from)
+    }
+
+    pub fn viewing_distance(&self, x: usize, y: usize, direction: Direction) -> Option<u32> {
+        self.view(x, y)
+            .map(|view| view.sightline(direction).distance)
+    }
+
+    pub fn scenic_score(&self, x: usize, y: usize) -> Option<u64> {
+        self.view(x, y).map(TreeView::scenic_score)
+    }
+
+    /// The position and score of the tree with the highest scenic score. Ties are resolved in
+    /// favour of the first tree in reading order.
+    pub fn best_spot(&self) -> Option<(usize, usize, u64)> {
+        self.positions()
+            .map(|(x, y)| (x, y, self.views[y][x].scenic_score()))
+            .fold(None, |best, spot| match best {
+                Some((_, _, best_score)) if best_score >= spot.2 => best,
+                _ => Some(spot),
+            })
+    }
+
+    pub fn visible_tree_count(&self) -> u32 {
+        self.views
+            .iter()
+            .flatten()
+            .filter(|view| view.is_visible())
+            .count() as u32
+    }
+
+    /// `#` for visible trees, `.` for hidden ones.
+    pub fn render_visibility_ascii(&self) -> String {
+        self.render_ascii(|view| if view.is_visible() { b'#' } else { b'.' })
+    }
+
+    /// Scenic scores shaded from ` ` (0) to `@` (the best spot) on a logarithmic scale.
+    pub fn render_scenic_score_ascii(&self) -> String {
+        let max_score = self.max_scenic_score();
+        self.render_ascii(|view| {
+            let shade = scenic_score_shade(view.scenic_score(), max_score);
+            ASCII_SHADES[shade as usize * (ASCII_SHADES.len() - 1) / 255]
+        })
+    }
+
+    /// Binary PPM (P6). Visible trees are drawn in orange, hidden ones in green, both brighter the
+    /// taller the tree.
+    pub fn write_visibility_ppm(&self, mut out: impl Write) -> io::Result<()> {
+        let max_size = self.tree_map.iter().flatten().max().copied().unwrap_or(0);
+        write!(out, "P6\n{} {}\n255\n", self.width(), self.height())?;
+
+        let mut pixels = Vec::with_capacity(self.width() * self.height() * 3);
+        for (x, y) in self.positions() {
+            let brightness = 55 + scale(self.tree_map[y][x] as u32, max_size as u32, 200);
+            if self.views[y][x].is_visible() {
+                pixels.extend_from_slice(&[brightness, brightness / 2, 0]);
+            } else {
+                pixels.extend_from_slice(&[0, brightness / 2, 0]);
+            }
+        }
+
+        out.write_all(&pixels)
+    }
+
+    /// Binary PGM (P5) with white for visible and black for hidden trees.
+    pub fn write_visibility_pgm(&self, out: impl Write) -> io::Result<()> {
+        self.write_pgm(out, |view| if view.is_visible() { 255 } else { 0 })
+    }
+
+    /// Binary PGM (P5) of the scenic scores on a logarithmic scale, the best spot being white.
+    pub fn write_scenic_score_pgm(&self, out: impl Write) -> io::Result<()> {
+        let max_score = self.max_scenic_score();
+        self.write_pgm(out, |view| {
+            scenic_score_shade(view.scenic_score(), max_score)
+        })
+    }
+
+    fn max_scenic_score(&self) -> u64 {
+        self.best_spot().map_or(0, |(_, _, score)| score)
+    }
+
+    fn positions(&self) -> impl Iterator<Item = (usize, usize)> {
+        let width = self.width();
+        (0..self.height()).flat_map(move |y| (0..width).map(move |x| (x, y)))
+    }
+
+    fn render_ascii(&self, shade: impl Fn(&TreeView) -> u8) -> String {
+        let mut output = String::with_capacity((self.width() + 1) * self.height());
+        for row in self.views.iter() {
+            output.extend(row.iter().map(|view| shade(view) as char));
+            output.push('\n');
+        }
+        output
+    }
+
+    fn write_pgm(&self, mut out: impl Write, shade: impl Fn(&TreeView) -> u8) -> io::Result<()> {
+        write!(out, "P5\n{} {}\n255\n", self.width(), self.height())?;
+        let pixels: Vec<u8> = self.views.iter().flatten().map(shade).collect();
+        out.write_all(&pixels)
+    }
+}
+
+/// Scenic scores span several orders of magnitude, so they are shaded logarithmically to keep
+/// the merely good spots distinguishable from the bad ones.
+fn scenic_score_shade(score: u64, max_score: u64) -> u8 {
+    if max_score == 0 {
+        return 0;
+    }
+
+    let shade = (score as f64).ln_1p() / (max_score as f64).ln_1p() * 255.0;
+    shade.round() as u8
+}
+
+fn scale(value: u32, max_value: u32, range: u32) -> u8 {
+    if max_value == 0 {
+        return 0;
+    }
+
+    (value * range / max_value) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tree_queries() {
+        let forest = Forest::parse(include_str!("../test.txt"));
+
+        assert_eq!((5, 5), (forest.width(), forest.height()));
+        assert_eq!(Some(5), forest.tree_size(2, 3));
+        assert_eq!(Some(true), forest.is_visible(2, 3));
+        assert_eq!(
+            Some(vec![Direction::Left, Direction::Down]),
+            forest.visible_from(2, 3)
+        );
+        assert_eq!(Some(2), forest.viewing_distance(2, 3, Direction::Up));
+        assert_eq!(Some(2), forest.viewing_distance(2, 3, Direction::Left));
+        assert_eq!(Some(1), forest.viewing_distance(2, 3, Direction::Down));
+        assert_eq!(Some(2), forest.viewing_distance(2, 3, Direction::Right));
+        assert_eq!(Some(8), forest.scenic_score(2, 3));
+        assert_eq!(Some((2, 3, 8)), forest.best_spot());
+        assert_eq!(Some(vec![]), forest.visible_from(2, 2));
+        assert_eq!(None, forest.view(5, 0));
+        assert_eq!(21, forest.visible_tree_count());
+    }
+
+    #[test]
+    fn test_render_visibility_ascii() {
+        let forest = Forest::parse(include_str!("../test.txt"));
+
+        let want = "#####\n###.#\n##.##\n#.#.#\n#####\n";
+
+        assert_eq!(want, forest.render_visibility_ascii());
+    }
+
+    #[test]
+    fn test_render_scenic_score_ascii() {
+        let forest = Forest::parse(include_str!("../test.txt"));
+
+        let rendered = forest.render_scenic_score_ascii();
+
+        // The edge trees have a score of 0, the best spot gets the densest shade.
+        assert_eq!("     ", rendered.lines().next().unwrap());
+        assert_eq!(Some('@'), rendered.lines().nth(3).unwrap().chars().nth(2));
+    }
+
+    #[test]
+    fn test_write_pgm_and_ppm() {
+        let forest = Forest::parse(include_str!("../test.txt"));
+
+        let mut pgm = Vec::new();
+        forest.write_scenic_score_pgm(&mut pgm).unwrap();
+        let header = b"P5\n5 5\n255\n";
+        assert_eq!(header, &pgm[..header.len()]);
+        assert_eq!(header.len() + 25, pgm.len());
+        assert_eq!(255, pgm[header.len() + 3 * 5 + 2]);
+
+        let mut ppm = Vec::new();
+        forest.write_visibility_ppm(&mut ppm).unwrap();
+        let header = b"P6\n5 5\n255\n";
+        assert_eq!(header, &ppm[..header.len()]);
+        assert_eq!(header.len() + 25 * 3, ppm.len());
+    }
+}
@@ -0,0 +1,400 @@
+mod forest;
+mod line_of_sight;
+
+pub use forest::Forest;
+pub use line_of_sight::{
+    parse_height_map, BlockingRule, Heading, HeightMap, LineOfSight, Neighbourhood, Survey,
+};
+
+type TreeMap = Vec<Vec<u8>>;
+
+/// How far a tree can see in one direction: the number of trees up to and including the first
+/// tree that is at least as tall, or up to the edge of the map.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Sightline {
+    pub distance: u32,
+    /// No tree in this direction is at least as tall, i.e. the tree is visible from this edge.
+    pub reaches_edge: bool,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct TreeView {
+    pub left: Sightline,
+    pub right: Sightline,
+    pub up: Sightline,
+    pub down: Sightline,
+}
+
+impl TreeView {
+    pub fn is_visible(&self) -> bool {
+        self.left.reaches_edge
+            || self.right.reaches_edge
+            || self.up.reaches_edge
+            || self.down.reaches_edge
+    }
+
+    pub fn scenic_score(&self) -> u64 {
+        [self.left, self.right, self.up, self.down]
+            .iter()
+            .map(|sightline| u64::from(sightline.distance))
+            .product()
+    }
+
+    pub fn sightline(&self, direction: Direction) -> Sightline {
+        match direction {
+            Direction::Left => self.left,
+            Direction::Right => self.right,
+            Direction::Up => self.up,
+            Direction::Down => self.down,
+        }
+    }
+
+    /// The edges from which the tree can be seen.
+    pub fn visible_from(&self) -> Vec<Direction> {
+        Direction::ALL
+            .into_iter()
+            .filter(|direction| self.sightline(*direction).reaches_edge)
+            .collect()
+    }
+}
+
+pub fn calculate_visible_trees(input: &str) -> u32 {
+    let tree_map = parse_tree_map(input);
+
+    count_visible_trees(&tree_map)
+}
+
+pub fn calculate_scenic_score(input: &str) -> u64 {
+    let tree_map = parse_tree_map(input);
+
+    max_scenic_score(&tree_map)
+}
+
+pub fn count_visible_trees(tree_map: &[Vec<u8>]) -> u32 {
+    let width = tree_map.first().map_or(0, |row| row.len());
+    let mut visible = vec![false; width * tree_map.len()];
+
+    sweep_sightlines(tree_map, |row, col, _, sightline| {
+        visible[row * width + col] |= sightline.reaches_edge;
+    });
+
+    visible.iter().filter(|is_visible| **is_visible).count() as u32
+}
+
+pub fn max_scenic_score(tree_map: &[Vec<u8>]) -> u64 {
+    let width = tree_map.first().map_or(0, |row| row.len());
+    let mut scenic_scores = vec![1u64; width * tree_map.len()];
+
+    sweep_sightlines(tree_map, |row, col, _, sightline| {
+        scenic_scores[row * width + col] *= u64::from(sightline.distance);
+    });
+
+    scenic_scores.into_iter().max().unwrap_or(0)
+}
+
+/// Calculates the sightlines of every tree in all four directions.
+pub fn calculate_tree_views(tree_map: &[Vec<u8>]) -> Vec<Vec<TreeView>> {
+    let width = tree_map.first().map_or(0, |row| row.len());
+    let mut views = vec![vec![TreeView::default(); width]; tree_map.len()];
+
+    sweep_sightlines(tree_map, |row, col, direction, sightline| {
+        let view = &mut views[row][col];
+        match direction {
+            Direction::Left => view.left = sightline,
+            Direction::Right => view.right = sightline,
+            Direction::Up => view.up = sightline,
+            Direction::Down => view.down = sightline,
+        }
+    });
+
+    views
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::Left,
+        Direction::Right,
+        Direction::Up,
+        Direction::Down,
+    ];
+}
+
+/// The trees along a line of sight that can still block the view of trees further along.
+///
+/// The stack is kept in strictly decreasing order of height: a tree that is not taller than the
+/// current one can never block anything behind the current one that the current tree would not
+/// block first, so it is popped for good. Whatever remains on top is the nearest tree that blocks
+/// the view: one that is at least as tall, or strictly taller if `equal_blocks` is unset. Every
+/// tree is pushed and popped at most once, which makes a sweep along a line linear in its length.
+pub(crate) struct BlockingTrees<T = u8> {
+    stack: Vec<(u32, T)>,
+    equal_blocks: bool,
+}
+
+impl<T: Copy + Ord> BlockingTrees<T> {
+    pub(crate) fn new(equal_blocks: bool) -> Self {
+        Self {
+            stack: Vec::new(),
+            equal_blocks,
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.stack.clear();
+    }
+
+    /// Adds the tree at position `step` of the line and returns its sightline back towards the
+    /// start of the line.
+    pub(crate) fn push(&mut self, step: usize, tree_size: T) -> Sightline {
+        let step = step as u32;
+        while self.stack.last().is_some_and(|(_, size)| {
+            *size < tree_size || (!self.equal_blocks && *size == tree_size)
+        }) {
+            self.stack.pop();
+        }
+
+        let sightline = match self.stack.last() {
+            Some((blocking_step, blocking_size)) => {
+                let distance = step - blocking_step;
+                if *blocking_size == tree_size {
+                    self.stack.pop();
+                }
+                Sightline {
+                    distance,
+                    reaches_edge: false,
+                }
+            }
+            None => Sightline {
+                distance: step,
+                reaches_edge: true,
+            },
+        };
+
+        self.stack.push((step, tree_size));
+        sightline
+    }
+}
+
+/// Reports the sightline of every tree in every direction in O(w·h) total.
+///
+/// Rows are swept left to right and right to left. Columns are swept with one stack per column
+/// while walking the map row by row, so that the tree map is always read in memory order.
+fn sweep_sightlines(
+    tree_map: &[Vec<u8>],
+    mut record: impl FnMut(usize, usize, Direction, Sightline),
+) {
+    let width = tree_map.first().map_or(0, |row| row.len());
+
+    let mut row_stack = BlockingTrees::new(true);
+    for (row, tree_row) in tree_map.iter().enumerate() {
+        row_stack.clear();
+        for (col, tree_size) in tree_row.iter().enumerate() {
+            record(row, col, Direction::Left, row_stack.push(col, *tree_size));
+        }
+
+        row_stack.clear();
+        for (step, (col, tree_size)) in tree_row.iter().enumerate().rev().enumerate() {
+            record(row, col, Direction::Right, row_stack.push(step, *tree_size));
+        }
+    }
+
+    let mut column_stacks: Vec<BlockingTrees> =
+        (0..width).map(|_| BlockingTrees::new(true)).collect();
+    for (row, tree_row) in tree_map.iter().enumerate() {
+        for (col, tree_size) in tree_row.iter().enumerate() {
+            record(
+                row,
+                col,
+                Direction::Up,
+                column_stacks[col].push(row, *tree_size),
+            );
+        }
+    }
+
+    column_stacks.iter_mut().for_each(BlockingTrees::clear);
+    for (step, (row, tree_row)) in tree_map.iter().enumerate().rev().enumerate() {
+        for (col, tree_size) in tree_row.iter().enumerate() {
+            record(
+                row,
+                col,
+                Direction::Down,
+                column_stacks[col].push(step, *tree_size),
+            );
+        }
+    }
+}
+
+/// The straightforward O(w·h·(w+h)) approach: walk outward from every tree until the view is
+/// blocked. Kept as a reference for the stack-based sweeps.
+pub fn max_scenic_score_by_walking(tree_map: &[Vec<u8>]) -> u64 {
+    let mut max_scenic_score = 0;
+
+    // the outer trees are irrelevant
+    for i in 1..tree_map.len().saturating_sub(1) {
+        for j in 1..tree_map[i].len() - 1 {
+            let scenic_score = u64::from(visible_trees_left(tree_map, tree_map[i][j], i, j))
+                * u64::from(visible_trees_right(tree_map, tree_map[i][j], i, j))
+                * u64::from(visible_trees_up(tree_map, tree_map[i][j], i, j))
+                * u64::from(visible_trees_down(tree_map, tree_map[i][j], i, j));
+
+            if scenic_score > max_scenic_score {
+                max_scenic_score = scenic_score;
+            }
+        }
+    }
+
+    max_scenic_score
+}
+
+fn visible_trees_down(tree_map: &[Vec<u8>], tree_size: u8, row_idx: usize, col_idx: usize) -> u32 {
+    let mut visible_trees = 1; // at least one tree is visible
+
+    for row in tree_map.iter().take(tree_map.len() - 1).skip(row_idx + 1) {
+        if row[col_idx] < tree_size {
+            visible_trees += 1;
+        } else {
+            break;
+        }
+    }
+
+    visible_trees
+}
+
+fn visible_trees_up(tree_map: &[Vec<u8>], tree_size: u8, row_idx: usize, col_idx: usize) -> u32 {
+    let mut visible_trees = 1; // at least one tree is visible
+
+    for row in tree_map[1..row_idx].iter().rev() {
+        if row[col_idx] < tree_size {
+            visible_trees += 1;
+        } else {
+            break;
+        }
+    }
+
+    visible_trees
+}
+
+fn visible_trees_right(tree_map: &[Vec<u8>], tree_size: u8, row_idx: usize, col_idx: usize) -> u32 {
+    let mut visible_trees = 1; // at least one tree is visible
+    let row = &tree_map[row_idx];
+
+    for size in &row[col_idx + 1..row.len() - 1] {
+        if *size < tree_size {
+            visible_trees += 1;
+        } else {
+            break;
+        }
+    }
+
+    visible_trees
+}
+
+fn visible_trees_left(tree_map: &[Vec<u8>], tree_size: u8, row_idx: usize, col_idx: usize) -> u32 {
+    let mut visible_trees = 1; // at least one tree is visible
+
+    for size in tree_map[row_idx][1..col_idx].iter().rev() {
+        if *size < tree_size {
+            visible_trees += 1;
+        } else {
+            break;
+        }
+    }
+
+    visible_trees
+}
+
+pub fn parse_tree_map(input: &str) -> TreeMap {
+    input
+        .lines()
+        .map(|line| {
+            line.chars()
+                .map(|c| c.to_digit(10).unwrap() as u8)
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_part1() {
+        let input = include_str!("../test.txt");
+        assert_eq!(21, calculate_visible_trees(input));
+    }
+
+    #[test]
+    fn test_part2() {
+        let input = include_str!("../test.txt");
+        assert_eq!(8, calculate_scenic_score(input));
+    }
+
+    #[test]
+    fn test_tree_views() {
+        let tree_map = parse_tree_map(include_str!("../test.txt"));
+
+        let views = calculate_tree_views(&tree_map);
+
+        // The middle 5 in the second row from the puzzle description, visible from the top and right.
+        assert_eq!(
+            TreeView {
+                left: Sightline {
+                    distance: 1,
+                    reaches_edge: false
+                },
+                right: Sightline {
+                    distance: 2,
+                    reaches_edge: true
+                },
+                up: Sightline {
+                    distance: 1,
+                    reaches_edge: true
+                },
+                down: Sightline {
+                    distance: 2,
+                    reaches_edge: false
+                },
+            },
+            views[1][2]
+        );
+        assert_eq!(4, views[1][2].scenic_score());
+        // The 5 in the middle of the fourth row is the best spot.
+        assert_eq!(8, views[3][2].scenic_score());
+        // The top-left 5 is only visible from the left and from the top.
+        assert!(views[1][1].left.reaches_edge && views[1][1].up.reaches_edge);
+        assert!(!views[1][1].right.reaches_edge && !views[1][1].down.reaches_edge);
+    }
+
+    #[test]
+    fn test_sweeps_agree_with_walking() {
+        let input = include_str!("../input.txt");
+        let tree_map = parse_tree_map(input);
+
+        assert_eq!(
+            max_scenic_score_by_walking(&tree_map),
+            max_scenic_score(&tree_map)
+        );
+    }
+
+    #[test]
+    fn test_scenic_scores_beyond_u32() {
+        // A single tall tree in the middle sees 260 trees in every direction.
+        let mut tree_map = vec![vec![0; 521]; 521];
+        tree_map[260][260] = 9;
+
+        assert_eq!(260u64.pow(4), max_scenic_score(&tree_map));
+        assert_eq!(
+            260u64.pow(4),
+            calculate_tree_views(&tree_map)[260][260].scenic_score()
+        );
+    }
+}
@@ -0,0 +1,509 @@
+use crate::{BlockingTrees, Direction, Sightline};
+
+/// A grid of tree heights without the single digit restriction of the puzzle input.
+pub type HeightMap = Vec<Vec<u32>>;
+
+/// The compass headings a tree can look towards. `N` is up, `E` is right.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Heading {
+    N,
+    NE,
+    E,
+    SE,
+    S,
+    SW,
+    W,
+    NW,
+}
+
+impl Heading {
+    fn delta(self) -> (isize, isize) {
+        match self {
+            Heading::N => (0, -1),
+            Heading::NE => (1, -1),
+            Heading::E => (1, 0),
+            Heading::SE => (1, 1),
+            Heading::S => (0, 1),
+            Heading::SW => (-1, 1),
+            Heading::W => (-1, 0),
+            Heading::NW => (-1, -1),
+        }
+    }
+}
+
+impl From<Direction> for Heading {
+    fn from(direction: Direction) -> Self {
+        match direction {
+            Direction::Left => Heading::W,
+            Direction::Right => Heading::E,
+            Direction::Up => Heading::N,
+            Direction::Down => Heading::S,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Neighbourhood {
+    /// Along the rows and columns only.
+    Four,
+    /// Along the rows, the columns and both diagonals.
+    Eight,
+}
+
+impl Neighbourhood {
+    pub fn headings(self) -> &'static [Heading] {
+        match self {
+            Neighbourhood::Four => &[Heading::N, Heading::E, Heading::S, Heading::W],
+            Neighbourhood::Eight => &[
+                Heading::N,
+                Heading::NE,
+                Heading::E,
+                Heading::SE,
+                Heading::S,
+                Heading::SW,
+                Heading::W,
+                Heading::NW,
+            ],
+        }
+    }
+}
+
+/// Which trees end a tree's view. The blocking tree itself is always seen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockingRule {
+    /// Only trees that are strictly taller block the view, trees of the same height are looked
+    /// past.
+    StrictlyTaller,
+    /// Trees that are at least as tall block the view, as in the puzzle.
+    EqualOrTaller,
+    /// Like `EqualOrTaller`, but the view also only passes over the given number of shorter trees:
+    /// the next shorter tree after that is the last one seen. `PassesOverShorter(0)` only sees the
+    /// neighbouring tree.
+    PassesOverShorter(u32),
+}
+
+/// Configures how far trees can see. The default is the puzzle's rules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineOfSight {
+    pub neighbourhood: Neighbourhood,
+    pub blocking: BlockingRule,
+}
+
+impl Default for LineOfSight {
+    fn default() -> Self {
+        Self::PUZZLE
+    }
+}
+
+/// The sightlines of every tree of a height map in every heading of a `LineOfSight`.
+pub struct Survey {
+    headings: &'static [Heading],
+    width: usize,
+    height: usize,
+    // Indexed by `(y * width + x) * headings.len() + heading index`.
+    sightlines: Vec<Sightline>,
+}
+
+impl LineOfSight {
+    pub const PUZZLE: LineOfSight = LineOfSight {
+        neighbourhood: Neighbourhood::Four,
+        blocking: BlockingRule::EqualOrTaller,
+    };
+
+    /// Calculates the sightlines of all trees. The two height-based rules are swept with a stack
+    /// along every line in O(w·h) per heading, `PassesOverShorter(k)` walks at most `k + 1`
+    /// trees from every tree.
+    pub fn survey(&self, heights: &[Vec<u32>]) -> Survey {
+        let height = heights.len();
+        let width = heights.first().map_or(0, |row| row.len());
+        assert!(
+            heights.iter().all(|row| row.len() == width),
+            "All rows of the height map must have the same length"
+        );
+
+        let headings = self.neighbourhood.headings();
+        let mut survey = Survey {
+            headings,
+            width,
+            height,
+            sightlines: vec![Sightline::default(); width * height * headings.len()],
+        };
+
+        for (heading_idx, heading) in headings.iter().enumerate() {
+            match self.blocking {
+                BlockingRule::StrictlyTaller => {
+                    sweep_lines(heights, &mut survey, heading_idx, *heading, false)
+                }
+                BlockingRule::EqualOrTaller => {
+                    sweep_lines(heights, &mut survey, heading_idx, *heading, true)
+                }
+                BlockingRule::PassesOverShorter(max_shorter) => {
+                    walk_lines(heights, &mut survey, heading_idx, *heading, max_shorter)
+                }
+            }
+        }
+
+        survey
+    }
+}
+
+impl Survey {
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn headings(&self) -> &'static [Heading] {
+        self.headings
+    }
+
+    pub fn sightline(&self, x: usize, y: usize, heading: Heading) -> Option<Sightline> {
+        let heading_idx = self.headings.iter().position(|h| *h == heading)?;
+        self.sightlines_of(x, y)
+            .map(|sightlines| sightlines[heading_idx])
+    }
+
+    /// A tree is visible if at least one of its sightlines reaches the edge of the map.
+    pub fn is_visible(&self, x: usize, y: usize) -> Option<bool> {
+        self.sightlines_of(x, y)
+            .map(|sightlines| sightlines.iter().any(|s| s.reaches_edge))
+    }
+
+    /// The product of the viewing distances in all headings.
+    pub fn scenic_score(&self, x: usize, y: usize) -> Option<u64> {
+        self.sightlines_of(x, y).map(scenic_score)
+    }
+
+    pub fn visible_tree_count(&self) -> u32 {
+        self.sightlines
+            .chunks(self.headings.len())
+            .filter(|sightlines| sightlines.iter().any(|s| s.reaches_edge))
+            .count() as u32
+    }
+
+    pub fn max_scenic_score(&self) -> u64 {
+        self.sightlines
+            .chunks(self.headings.len())
+            .map(scenic_score)
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn sightlines_of(&self, x: usize, y: usize) -> Option<&[Sightline]> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        let start = (y * self.width + x) * self.headings.len();
+        Some(&self.sightlines[start..start + self.headings.len()])
+    }
+
+    fn record(&mut self, pos: (usize, usize), heading_idx: usize, sightline: Sightline) {
+        let (x, y) = pos;
+        self.sightlines[(y * self.width + x) * self.headings.len() + heading_idx] = sightline;
+    }
+}
+
+fn scenic_score(sightlines: &[Sightline]) -> u64 {
+    sightlines.iter().map(|s| s.distance as u64).product()
+}
+
+fn step(survey: &Survey, pos: (usize, usize), delta: (isize, isize)) -> Option<(usize, usize)> {
+    let x = pos.0.checked_add_signed(delta.0)?;
+    let y = pos.1.checked_add_signed(delta.1)?;
+    (x < survey.width && y < survey.height).then_some((x, y))
+}
+
+/// Sweeps every line of the map that runs along `heading` with a stack of blocking trees. A line
+/// starts at the edge `heading` points to and is swept backwards, so that every tree's sightline
+/// back towards the start of the sweep is its view in `heading`.
+fn sweep_lines(
+    heights: &[Vec<u32>],
+    survey: &mut Survey,
+    heading_idx: usize,
+    heading: Heading,
+    equal_blocks: bool,
+) {
+    let (dx, dy) = heading.delta();
+    let mut blocking_trees = BlockingTrees::new(equal_blocks);
+
+    for y in 0..survey.height {
+        for x in 0..survey.width {
+            if step(survey, (x, y), (dx, dy)).is_some() {
+                continue;
+            }
+
+            blocking_trees.clear();
+            let mut pos = Some((x, y));
+            let mut line_step = 0;
+            while let Some(current) = pos {
+                let sightline = blocking_trees.push(line_step, heights[current.1][current.0]);
+                survey.record(current, heading_idx, sightline);
+                pos = step(survey, current, (-dx, -dy));
+                line_step += 1;
+            }
+        }
+    }
+}
+
+fn walk_lines(
+    heights: &[Vec<u32>],
+    survey: &mut Survey,
+    heading_idx: usize,
+    heading: Heading,
+    max_shorter: u32,
+) {
+    let delta = heading.delta();
+
+    for y in 0..survey.height {
+        for x in 0..survey.width {
+            let tree_size = heights[y][x];
+            let mut sightline = Sightline {
+                distance: 0,
+                reaches_edge: true,
+            };
+            let mut shorter_trees = 0;
+            let mut pos = (x, y);
+
+            while let Some(next) = step(survey, pos, delta) {
+                sightline.distance += 1;
+                if heights[next.1][next.0] >= tree_size {
+                    sightline.reaches_edge = false;
+                    break;
+                }
+                if shorter_trees == max_shorter {
+                    // The last tree seen may still be the one on the edge.
+                    sightline.reaches_edge = step(survey, next, delta).is_none();
+                    break;
+                }
+                shorter_trees += 1;
+                pos = next;
+            }
+
+            survey.record((x, y), heading_idx, sightline);
+        }
+    }
+}
+
+/// Parses a grid of heights. Rows are lines, heights are separated by whitespace or commas. A map
+/// without any separator is read as single digit heights, like the puzzle input. Once any line
+/// has a separator, every line is read as separated heights, so a single column of heights above
+/// 9 needs at least one separator, e.g. a trailing comma.
+pub fn parse_height_map(input: &str) -> HeightMap {
+    let is_separator = |c: char| c == ',' || c.is_whitespace();
+    let lines: Vec<&str> = input
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+    let separated = lines.iter().any(|line| line.contains(is_separator));
+
+    lines
+        .into_iter()
+        .map(|line| {
+            if separated {
+                line.split(is_separator)
+                    .filter(|token| !token.is_empty())
+                    .map(|token| {
+                        token
+                            .parse()
+                            .unwrap_or_else(|_| panic!("Invalid tree height '{}'", token))
+                    })
+                    .collect()
+            } else {
+                line.chars()
+                    .map(|c| {
+                        c.to_digit(10)
+                            .unwrap_or_else(|| panic!("Invalid tree height '{}'", c))
+                    })
+                    .collect()
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{calculate_scenic_score, calculate_visible_trees};
+
+    #[test]
+    fn test_puzzle_preset_agrees_with_puzzle_rules() {
+        for input in [include_str!("../test.txt"), include_str!("../input.txt")] {
+            let survey = LineOfSight::PUZZLE.survey(&parse_height_map(input));
+
+            assert_eq!(calculate_visible_trees(input), survey.visible_tree_count());
+            assert_eq!(calculate_scenic_score(input), survey.max_scenic_score());
+        }
+    }
+
+    #[test]
+    fn test_parse_height_map() {
+        let want = vec![vec![10, 2, 300], vec![4, 5, 6]];
+
+        assert_eq!(want, parse_height_map("10 2 300\n4,5,6\n"));
+        assert_eq!(want, parse_height_map("10,  2,300\n 4 5 6 \n\n"));
+        assert_eq!(vec![vec![3, 0, 7]], parse_height_map("307"));
+        assert_eq!(vec![vec![12], vec![3, 4]], parse_height_map("12\n3 4"));
+        assert_eq!(
+            vec![vec![10], vec![20], vec![300]],
+            parse_height_map("10\n20\n300,\n")
+        );
+    }
+
+    #[test]
+    fn test_strictly_taller_looks_past_equal_trees() {
+        let heights = parse_height_map("5 5 5 3 5 6 1");
+        let line_of_sight = LineOfSight {
+            blocking: BlockingRule::StrictlyTaller,
+            ..LineOfSight::PUZZLE
+        };
+
+        let survey = line_of_sight.survey(&heights);
+
+        assert_eq!(
+            Some(Sightline {
+                distance: 4,
+                reaches_edge: false
+            }),
+            survey.sightline(1, 0, Heading::E)
+        );
+        assert_eq!(
+            Some(Sightline {
+                distance: 1,
+                reaches_edge: true
+            }),
+            survey.sightline(1, 0, Heading::W)
+        );
+        assert_eq!(
+            Some(Sightline {
+                distance: 1,
+                reaches_edge: false
+            }),
+            LineOfSight::PUZZLE
+                .survey(&heights)
+                .sightline(1, 0, Heading::E)
+        );
+    }
+
+    #[test]
+    fn test_passes_over_shorter_trees() {
+        let heights = parse_height_map("9 1 2 1 3 1 9");
+        let line_of_sight = LineOfSight {
+            blocking: BlockingRule::PassesOverShorter(2),
+            ..LineOfSight::PUZZLE
+        };
+
+        let survey = line_of_sight.survey(&heights);
+
+        // Passes over 1 and 2, the second 1 is the last tree seen.
+        assert_eq!(
+            Some(Sightline {
+                distance: 3,
+                reaches_edge: false
+            }),
+            survey.sightline(0, 0, Heading::E)
+        );
+        // Passes over 1, then the 9 blocks.
+        assert_eq!(
+            Some(Sightline {
+                distance: 2,
+                reaches_edge: false
+            }),
+            survey.sightline(4, 0, Heading::E)
+        );
+        // Passes over 1 and 2, the next 1 is the last tree seen before the 9.
+        assert_eq!(
+            Some(Sightline {
+                distance: 3,
+                reaches_edge: false
+            }),
+            survey.sightline(4, 0, Heading::W)
+        );
+    }
+
+    #[test]
+    fn test_passes_over_shorter_trees_to_the_edge() {
+        let line_of_sight = LineOfSight {
+            blocking: BlockingRule::PassesOverShorter(1),
+            ..LineOfSight::PUZZLE
+        };
+
+        // Passes over 1, the 2 on the edge is the last tree seen.
+        assert_eq!(
+            Some(Sightline {
+                distance: 2,
+                reaches_edge: true
+            }),
+            line_of_sight
+                .survey(&parse_height_map("5 1 2"))
+                .sightline(0, 0, Heading::E)
+        );
+        // The 2 is still the last tree seen, but no longer on the edge.
+        assert_eq!(
+            Some(Sightline {
+                distance: 2,
+                reaches_edge: false
+            }),
+            line_of_sight
+                .survey(&parse_height_map("5 1 2 1"))
+                .sightline(0, 0, Heading::E)
+        );
+        // A tall enough tree on the edge still blocks the view.
+        assert_eq!(
+            Some(Sightline {
+                distance: 2,
+                reaches_edge: false
+            }),
+            line_of_sight
+                .survey(&parse_height_map("5 1 5"))
+                .sightline(0, 0, Heading::E)
+        );
+    }
+
+    #[test]
+    fn test_diagonals() {
+        let heights = parse_height_map(include_str!("../test.txt"));
+        let line_of_sight = LineOfSight {
+            neighbourhood: Neighbourhood::Eight,
+            ..LineOfSight::PUZZLE
+        };
+
+        let survey = line_of_sight.survey(&heights);
+
+        // The 5 in the middle of the fourth row looks over the 3 and the 2 to its upper right to
+        // the edge, and is blocked by the 5s to its upper and lower left.
+        assert_eq!(
+            Some(Sightline {
+                distance: 2,
+                reaches_edge: true
+            }),
+            survey.sightline(2, 3, Heading::NE)
+        );
+        assert_eq!(
+            Some(Sightline {
+                distance: 1,
+                reaches_edge: false
+            }),
+            survey.sightline(2, 3, Heading::NW)
+        );
+        assert_eq!(
+            Some(Sightline {
+                distance: 1,
+                reaches_edge: false
+            }),
+            survey.sightline(2, 3, Heading::SW)
+        );
+        assert_eq!(
+            survey.sightline(2, 3, Heading::from(Direction::Up)),
+            survey.sightline(2, 3, Heading::N)
+        );
+        // The 4 in the fourth row is hidden along the axes, but can be seen from the upper right.
+        assert_eq!(Some(true), survey.is_visible(3, 3));
+        assert_eq!(Some(false), survey.is_visible(2, 2));
+        assert_eq!(None, survey.sightline(5, 0, Heading::N));
+    }
+}
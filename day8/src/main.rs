@@ -1,285 +1,124 @@
-struct Tree {
-    size: u8,
-    visible: bool,
-}
-
+use std::fs::File;
+use std::io::BufWriter;
+
+use day8::{
+    calculate_scenic_score, calculate_visible_trees, parse_height_map, BlockingRule, Direction,
+    Forest, LineOfSight, Neighbourhood,
+};
+
+/// Besides the puzzle results, the forest can be inspected and exported:
+///
+/// `day8 [--tree <x>,<y>]... [--ascii] [--visibility-ppm <file>] [--visibility-pgm <file>]
+/// [--scenic-pgm <file>]`
+///
+/// or surveyed with other line-of-sight rules, optionally from a grid of multi-digit heights:
+///
+/// `day8 survey [--diagonals] [--blocking strictly-taller|equal-or-taller|passes-over:<k>]
+/// [<grid file>]`
 fn main() {
     let input = include_str!("../input.txt");
 
+    if std::env::args().nth(1).as_deref() == Some("survey") {
+        survey(std::env::args().skip(2), input);
+        return;
+    }
+
     let result_part1 = calculate_visible_trees(input);
     let result_part2 = calculate_scenic_score(input);
 
     println!("Result part 1: {}", result_part1);
     println!("Result part 2: {}", result_part2);
-}
-
-fn calculate_scenic_score(input: &str) -> u32 {
-    let tree_map = init_tree_map(input);
-    let scenic_score = calculate_scenic_score_for_each_tree(&tree_map);
-
-    scenic_score
-}
-
-fn calculate_scenic_score_for_each_tree(tree_map: &Vec<Vec<Tree>>) -> u32 {
-    let mut max_scenic_score = 0;
-
-    // the outer trees are irrelevant
-    for i in 1..tree_map.len() - 1 {
-        for j in 1..tree_map[i].len() - 1 {
-            let scenic_score = visible_trees_left(&tree_map, tree_map[i][j].size, i, j)
-                * visible_trees_right(&tree_map, tree_map[i][j].size, i, j)
-                * visible_trees_up(&tree_map, tree_map[i][j].size, i, j)
-                * visible_trees_down(&tree_map, tree_map[i][j].size, i, j);
-
-            if scenic_score > max_scenic_score {
-                max_scenic_score = scenic_score;
-            }
-        }
-    }
-
-    max_scenic_score
-}
-
-fn visible_trees_down(
-    tree_map: &Vec<Vec<Tree>>,
-    tree_size: u8,
-    row_idx: usize,
-    col_idx: usize,
-) -> u32 {
-    let mut visible_trees = 1; // at least one tree is visible
-
-    for i in row_idx + 1..tree_map.len() - 1 {
-        if tree_map[i][col_idx].size < tree_size {
-            visible_trees += 1;
-        } else {
-            break;
-        }
-    }
-
-    visible_trees
-}
-fn visible_trees_up(
-    tree_map: &Vec<Vec<Tree>>,
-    tree_size: u8,
-    row_idx: usize,
-    col_idx: usize,
-) -> u32 {
-    let mut visible_trees = 1; // at least one tree is visible
 
-    for i in (1..row_idx).rev() {
-        if tree_map[i][col_idx].size < tree_size {
-            visible_trees += 1;
-        } else {
-            break;
-        }
-    }
-
-    visible_trees
-}
-
-fn visible_trees_right(
-    tree_map: &Vec<Vec<Tree>>,
-    tree_size: u8,
-    row_idx: usize,
-    col_idx: usize,
-) -> u32 {
-    let mut visible_trees = 1; // at least one tree is visible
-
-    for i in col_idx + 1..tree_map[row_idx].len() - 1 {
-        if tree_map[row_idx][i].size < tree_size {
-            visible_trees += 1;
-        } else {
-            break;
-        }
-    }
-
-    visible_trees
-}
-
-fn visible_trees_left(
-    tree_map: &Vec<Vec<Tree>>,
-    tree_size: u8,
-    row_idx: usize,
-    col_idx: usize,
-) -> u32 {
-    let mut visible_trees = 1; // at least one tree is visible
-
-    for i in (1..col_idx).rev() {
-        if tree_map[row_idx][i].size < tree_size {
-            visible_trees += 1;
-        } else {
-            break;
-        }
+    let mut args = std::env::args().skip(1).peekable();
+    if args.peek().is_none() {
+        return;
     }
 
-    visible_trees
-}
-
-fn calculate_visible_trees(input: &str) -> u32 {
-    let mut visible_tree_count = 0;
-
-    let mut tree_map = init_tree_map(input);
-
-    tree_map = scan_visible_trees_rowwise(tree_map);
-    tree_map = scan_visible_trees_columnwise(tree_map);
-
-    for row in tree_map {
-        for tree in row {
-            if tree.visible {
-                visible_tree_count += 1;
+    let forest = Forest::parse(input);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--tree" => print_tree(&forest, &args.next().expect("--tree requires <x>,<y>")),
+            "--ascii" => {
+                println!("{}", forest.render_visibility_ascii());
+                println!("{}", forest.render_scenic_score_ascii());
             }
+            "--visibility-ppm" => forest
+                .write_visibility_ppm(create_file(args.next()))
+                .unwrap(),
+            "--visibility-pgm" => forest
+                .write_visibility_pgm(create_file(args.next()))
+                .unwrap(),
+            "--scenic-pgm" => forest
+                .write_scenic_score_pgm(create_file(args.next()))
+                .unwrap(),
+            _ => panic!("Unknown argument {}", arg),
         }
     }
-
-    return visible_tree_count;
 }
 
-fn scan_visible_trees_rowwise(mut tree_map: Vec<Vec<Tree>>) -> Vec<Vec<Tree>> {
-    for mut row in tree_map.iter_mut() {
-        row = scan_visible_trees_row_lefttoright(row);
-        scan_visible_trees_row_righttoleft(row);
-    }
-
-    tree_map
-}
+fn survey(mut args: impl Iterator<Item = String>, input: &str) {
+    let mut line_of_sight = LineOfSight::default();
+    let mut grid: Option<String> = None;
 
-fn scan_visible_trees_row_righttoleft(trees: &mut Vec<Tree>) -> &mut Vec<Tree> {
-    let mut tree_size_to_be_visible = 0;
-    let mut is_edge = true;
-
-    for tree in trees.iter_mut().rev() {
-        if is_edge {
-            tree.visible = true;
-            is_edge = false;
-            tree_size_to_be_visible = tree.size;
-            continue;
-        }
-
-        if tree.size > tree_size_to_be_visible {
-            tree.visible = true;
-            tree_size_to_be_visible = tree.size;
-        }
-    }
-
-    trees
-}
-
-fn scan_visible_trees_row_lefttoright(trees: &mut Vec<Tree>) -> &mut Vec<Tree> {
-    let mut tree_size_to_be_visible = 0;
-    let mut is_edge = true;
-
-    for tree in trees.iter_mut() {
-        if is_edge {
-            tree.visible = true;
-            is_edge = false;
-            tree_size_to_be_visible = tree.size;
-            continue;
-        }
-
-        if tree.size > tree_size_to_be_visible {
-            tree.visible = true;
-            tree_size_to_be_visible = tree.size;
-        }
-    }
-
-    trees
-}
-
-fn scan_visible_trees_columnwise(mut tree_map: Vec<Vec<Tree>>) -> Vec<Vec<Tree>> {
-    for idx in 0..tree_map[0].len() {
-        tree_map = scan_visible_trees_column_downward(tree_map, idx);
-        tree_map = scan_visible_trees_column_upward(tree_map, idx);
-    }
-
-    tree_map
-}
-
-fn scan_visible_trees_column_upward(
-    mut tree_map: Vec<Vec<Tree>>,
-    col_idx: usize,
-) -> Vec<Vec<Tree>> {
-    let mut tree_size_to_be_visible = 0;
-    let mut is_edge = true;
-
-    for tree_row in tree_map.iter_mut().rev() {
-        let tree = &mut tree_row[col_idx];
-
-        if is_edge {
-            tree.visible = true;
-            is_edge = false;
-            tree_size_to_be_visible = tree.size;
-            continue;
-        }
-
-        if tree.size > tree_size_to_be_visible {
-            tree.visible = true;
-            tree_size_to_be_visible = tree.size;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--diagonals" => line_of_sight.neighbourhood = Neighbourhood::Eight,
+            "--blocking" => {
+                line_of_sight.blocking = parse_blocking_rule(&args.next().unwrap_or_default())
+            }
+            _ => grid = Some(arg),
         }
     }
 
-    tree_map
-}
-
-fn scan_visible_trees_column_downward(
-    mut tree_map: Vec<Vec<Tree>>,
-    col_idx: usize,
-) -> Vec<Vec<Tree>> {
-    let mut tree_size_to_be_visible = 0;
-    let mut is_edge = true;
-
-    for tree_row in tree_map.iter_mut() {
-        let tree = &mut tree_row[col_idx];
-
-        if is_edge {
-            tree.visible = true;
-            is_edge = false;
-            tree_size_to_be_visible = tree.size;
-            continue;
-        }
-
-        if tree.size > tree_size_to_be_visible {
-            tree.visible = true;
-            tree_size_to_be_visible = tree.size;
-        }
+    let heights = match grid {
+        Some(path) => parse_height_map(
+            &std::fs::read_to_string(&path).unwrap_or_else(|err| panic!("{}: {}", path, err)),
+        ),
+        None => parse_height_map(input),
+    };
+    let survey = line_of_sight.survey(&heights);
+
+    println!("Visible trees: {}", survey.visible_tree_count());
+    println!("Highest scenic score: {}", survey.max_scenic_score());
+}
+
+fn parse_blocking_rule(rule: &str) -> BlockingRule {
+    match rule {
+        "strictly-taller" => BlockingRule::StrictlyTaller,
+        "equal-or-taller" => BlockingRule::EqualOrTaller,
+        _ => rule
+            .strip_prefix("passes-over:")
+            .and_then(|k| k.parse().ok())
+            .map(BlockingRule::PassesOverShorter)
+            .unwrap_or_else(|| panic!("Unknown blocking rule '{}'", rule)),
     }
-
-    tree_map
 }
 
-fn init_tree_map(input: &str) -> Vec<Vec<Tree>> {
-    let mut tree_map: Vec<Vec<Tree>> = Vec::new();
-
-    for line in input.lines() {
-        let mut tree_line: Vec<Tree> = Vec::new();
-
-        for tree_size in line.chars().map(|c| c.to_digit(10).unwrap()) {
-            let tree = Tree {
-                size: tree_size as u8,
-                visible: false,
-            };
-
-            tree_line.push(tree);
-        }
-
-        tree_map.push(tree_line);
+fn print_tree(forest: &Forest, pos: &str) {
+    let (x, y) = pos
+        .split_once(',')
+        .map(|(x, y)| (x.parse().unwrap(), y.parse().unwrap()))
+        .expect("Expected a position like 3,7");
+
+    let Some(view) = forest.view(x, y) else {
+        println!("({}, {}) is outside of the forest", x, y);
+        return;
+    };
+
+    println!("Tree at ({}, {})", x, y);
+    println!("  height: {}", forest.tree_size(x, y).unwrap());
+    println!("  visible from: {:?}", view.visible_from());
+    for direction in Direction::ALL {
+        println!(
+            "  viewing distance {:?}: {}",
+            direction,
+            view.sightline(direction).distance
+        );
     }
-
-    return tree_map;
+    println!("  scenic score: {}", view.scenic_score());
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_part1() {
-        let input = include_str!("../test.txt");
-        assert_eq!(21, calculate_visible_trees(input));
-    }
-
-    #[test]
-    fn test_part2() {
-        let input = include_str!("../test.txt");
-        assert_eq!(8, calculate_scenic_score(input));
-    }
+fn create_file(path: Option<String>) -> BufWriter<File> {
+    let path = path.expect("Expected an output file");
+    BufWriter::new(File::create(&path).unwrap_or_else(|err| panic!("{}: {}", path, err)))
 }
@@ -0,0 +1,23 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use day9::{count_visited_tail_pos, parse_instructions, Rope};
+
+pub fn criterion_benchmark(c: &mut Criterion) {
+    let instructions = parse_instructions(include_str!("../input.txt"));
+
+    let mut group = c.benchmark_group("visited tail positions");
+    for rope_length in [10, 100, 1000] {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(rope_length),
+            &rope_length,
+            |b, rope_length| {
+                b.iter(|| {
+                    count_visited_tail_pos(&instructions, Rope::new(black_box(*rope_length)))
+                });
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,300 @@
+use std::collections::HashSet;
+
+mod render;
+mod trajectory;
+
+pub use render::{animate, write_frames, Bounds, KnotStyle, Renderer};
+pub use trajectory::Trajectory;
+
+/// A position on the puzzle's plane, `x` to the right and `y` downwards.
+pub type Pos = (i32, i32);
+/// A position in space. Ropes only leave the plane `z = 0` when moved forward or backward.
+pub type Pos3 = (i32, i32, i32);
+
+/// Drops the third axis, for drawing ropes on the plane.
+pub fn planar((x, y, _): Pos3) -> Pos {
+    (x, y)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
+    Forward,
+    Backward,
+}
+
+impl Direction {
+    pub fn parse(direction: &str) -> Direction {
+        match direction {
+            "U" => Direction::Up,
+            "D" => Direction::Down,
+            "L" => Direction::Left,
+            "R" => Direction::Right,
+            "UL" => Direction::UpLeft,
+            "UR" => Direction::UpRight,
+            "DL" => Direction::DownLeft,
+            "DR" => Direction::DownRight,
+            "F" => Direction::Forward,
+            "B" => Direction::Backward,
+            _ => panic!("Unknown direction {}", direction),
+        }
+    }
+
+    fn delta(self) -> Pos3 {
+        match self {
+            Direction::Up => (0, -1, 0),
+            Direction::Down => (0, 1, 0),
+            Direction::Left => (-1, 0, 0),
+            Direction::Right => (1, 0, 0),
+            Direction::UpLeft => (-1, -1, 0),
+            Direction::UpRight => (1, -1, 0),
+            Direction::DownLeft => (-1, 1, 0),
+            Direction::DownRight => (1, 1, 0),
+            Direction::Forward => (0, 0, 1),
+            Direction::Backward => (0, 0, -1),
+        }
+    }
+}
+
+/// A rope as the positions of its knots, head first. Every knot follows the one in front of it
+/// as soon as it is more than `slack` steps away along any axis, by moving one step towards it
+/// along each axis. The puzzle's rope has a slack of 1, so knots follow once they stop touching.
+#[derive(Debug, Clone)]
+pub struct Rope {
+    knots: Vec<Pos3>,
+    slack: i32,
+    trace: bool,
+}
+
+impl Rope {
+    /// A rope of `length` knots with the puzzle's slack of 1, all starting at `(0, 0, 0)`.
+    pub fn new(length: usize) -> Self {
+        assert!(length > 0, "A rope needs at least one knot");
+        Self {
+            knots: vec![(0, 0, 0); length],
+            slack: 1,
+            trace: false,
+        }
+    }
+
+    /// Lets knots lag up to `slack` steps behind the knot in front of them before following.
+    pub fn with_slack(mut self, slack: u32) -> Self {
+        self.slack = slack.try_into().expect("Slack too large");
+        self
+    }
+
+    /// Prints every knot movement.
+    pub fn traced(mut self) -> Self {
+        self.trace = true;
+        self
+    }
+
+    pub fn knots(&self) -> &[Pos3] {
+        &self.knots
+    }
+
+    pub fn head(&self) -> Pos3 {
+        self.knots[0]
+    }
+
+    pub fn tail(&self) -> Pos3 {
+        self.knots[self.knots.len() - 1]
+    }
+
+    /// Moves the head one step and lets the rest of the rope catch up.
+    pub fn step(&mut self, direction: Direction) {
+        let (dx, dy, dz) = direction.delta();
+        self.knots[0].0 += dx;
+        self.knots[0].1 += dy;
+        self.knots[0].2 += dz;
+        if self.trace {
+            println!("Head moved {:?} to {:?}.", direction, self.knots[0]);
+        }
+
+        for idx in 1..self.knots.len() {
+            let leader = self.knots[idx - 1];
+            let follower = &mut self.knots[idx];
+            let (dx, dy, dz) = (
+                leader.0 - follower.0,
+                leader.1 - follower.1,
+                leader.2 - follower.2,
+            );
+            if dx.abs() <= self.slack && dy.abs() <= self.slack && dz.abs() <= self.slack {
+                // Once a knot stays put, all knots behind it do as well.
+                break;
+            }
+
+            follower.0 += dx.signum();
+            follower.1 += dy.signum();
+            follower.2 += dz.signum();
+            if self.trace {
+                println!("Knot {} moved to {:?}.", idx, follower);
+            }
+        }
+    }
+}
+
+pub fn parse_instructions(input: &str) -> Vec<(Direction, u32)> {
+    input
+        .lines()
+        .map(|line| {
+            let (direction, step_count) = line.split_once(' ').unwrap();
+            (Direction::parse(direction), step_count.parse().unwrap())
+        })
+        .collect()
+}
+
+pub fn calculate_visited_tail_pos(input: &str, rope_length: usize) -> u32 {
+    count_visited_tail_pos(&parse_instructions(input), Rope::new(rope_length))
+}
+
+/// Counts the distinct positions in space the tail of `rope` visits, which are the puzzle's
+/// positions on the plane as long as the instructions never move forward or backward.
+pub fn count_visited_tail_pos(instructions: &[(Direction, u32)], rope: Rope) -> u32 {
+    let mut tail_visited_positions = HashSet::new();
+    tail_visited_positions.insert(rope.tail());
+
+    simulate(instructions, rope, |rope| {
+        tail_visited_positions.insert(rope.tail());
+    });
+
+    tail_visited_positions.len() as u32
+}
+
+/// Moves the rope through all instructions and calls `on_step` after every single step.
+pub fn simulate(instructions: &[(Direction, u32)], mut rope: Rope, mut on_step: impl FnMut(&Rope)) {
+    for (direction, step_count) in instructions {
+        for _ in 0..*step_count {
+            rope.step(*direction);
+            on_step(&rope);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_part1() {
+        let input = include_str!("../test1.txt");
+
+        assert_eq!(calculate_visited_tail_pos(input, 2), 13);
+    }
+
+    #[test]
+    fn test_part2_smaller_set() {
+        let input = include_str!("../test1.txt");
+
+        assert_eq!(calculate_visited_tail_pos(input, 10), 1);
+    }
+
+    #[test]
+    fn test_part2_bigger_set() {
+        let input = include_str!("../test2.txt");
+
+        assert_eq!(calculate_visited_tail_pos(input, 10), 36);
+    }
+
+    #[test]
+    fn test_input() {
+        let input = include_str!("../input.txt");
+
+        assert_eq!(calculate_visited_tail_pos(input, 2), 6266);
+        assert_eq!(calculate_visited_tail_pos(input, 10), 2369);
+    }
+
+    #[test]
+    fn test_single_knot_rope_is_its_own_tail() {
+        let input = include_str!("../test1.txt");
+
+        // Every position the head passes, (4, -3) and (4, -2) are crossed twice.
+        assert_eq!(calculate_visited_tail_pos(input, 1), 21);
+    }
+
+    #[test]
+    fn test_rope_positions() {
+        let mut rope = Rope::new(3);
+
+        rope.step(Direction::Right);
+        rope.step(Direction::Right);
+        rope.step(Direction::Up);
+        rope.step(Direction::Up);
+
+        assert_eq!(&[(2, -2, 0), (2, -1, 0), (1, -1, 0)], rope.knots());
+    }
+
+    #[test]
+    fn test_parse_diagonal_and_spatial_moves() {
+        let instructions = parse_instructions("UL 1\nDR 12\nF 3\nB 4\n");
+
+        assert_eq!(
+            vec![
+                (Direction::UpLeft, 1),
+                (Direction::DownRight, 12),
+                (Direction::Forward, 3),
+                (Direction::Backward, 4),
+            ],
+            instructions
+        );
+    }
+
+    #[test]
+    fn test_diagonal_head_moves() {
+        let mut rope = Rope::new(2);
+
+        rope.step(Direction::UpRight);
+        assert_eq!((0, 0, 0), rope.tail());
+        rope.step(Direction::UpRight);
+        assert_eq!((1, -1, 0), rope.tail());
+        rope.step(Direction::Right);
+        rope.step(Direction::DownRight);
+        assert_eq!((3, -1, 0), rope.tail());
+    }
+
+    #[test]
+    fn test_slack() {
+        let instructions = parse_instructions("R 5\nU 2\n");
+
+        let mut rope = Rope::new(2).with_slack(3);
+        for direction in [Direction::Right; 5] {
+            rope.step(direction);
+        }
+        assert_eq!((2, 0, 0), rope.tail());
+
+        // The tail only gets pulled along twice, to (1, 0) and (2, 0).
+        assert_eq!(
+            3,
+            count_visited_tail_pos(&instructions, Rope::new(2).with_slack(3))
+        );
+        assert_eq!(
+            1,
+            count_visited_tail_pos(&instructions, Rope::new(2).with_slack(5))
+        );
+    }
+
+    #[test]
+    fn test_spatial_rope() {
+        let instructions = parse_instructions("F 3\nR 2\nB 6\n");
+
+        let mut rope = Rope::new(2);
+        for (direction, step_count) in &instructions {
+            for _ in 0..*step_count {
+                rope.step(*direction);
+            }
+        }
+        assert_eq!((2, 0, -3), rope.head());
+        assert_eq!((2, 0, -2), rope.tail());
+
+        // The start, two steps forward, one diagonal step in space to (1, 0, 3) and five steps
+        // from (2, 0, 2) backwards.
+        assert_eq!(9, count_visited_tail_pos(&instructions, Rope::new(2)));
+    }
+}
@@ -0,0 +1,290 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+use crate::{planar, simulate, Direction, Pos, Pos3, Rope};
+
+/// Knot labels from right behind the head to the tail for ropes that are too long for digits.
+const GRADIENT: &[u8] = b"%&$@Oo*+=~-:";
+
+/// The area covered by a simulation, so that all of its frames share the same grid.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub min: Pos,
+    pub max: Pos,
+}
+
+impl Bounds {
+    /// Runs the simulation once with `rope` to find the area all knots stay in on the plane,
+    /// including the start.
+    pub fn of_simulation(instructions: &[(Direction, u32)], rope: &Rope) -> Self {
+        let mut bounds = Bounds {
+            min: (0, 0),
+            max: (0, 0),
+        };
+        simulate(instructions, rope.clone(), |rope| {
+            rope.knots()
+                .iter()
+                .for_each(|knot| bounds.include(planar(*knot)));
+        });
+        bounds
+    }
+
+    pub fn include(&mut self, pos: Pos) {
+        self.min = (self.min.0.min(pos.0), self.min.1.min(pos.1));
+        self.max = (self.max.0.max(pos.0), self.max.1.max(pos.1));
+    }
+
+    pub fn width(&self) -> usize {
+        (self.max.0 - self.min.0 + 1) as usize
+    }
+
+    pub fn height(&self) -> usize {
+        (self.max.1 - self.min.1 + 1) as usize
+    }
+
+    pub(crate) fn index_of(&self, pos: Pos) -> Option<usize> {
+        if pos.0 < self.min.0 || pos.0 > self.max.0 || pos.1 < self.min.1 || pos.1 > self.max.1 {
+            return None;
+        }
+
+        Some((pos.1 - self.min.1) as usize * self.width() + (pos.0 - self.min.0) as usize)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KnotStyle {
+    /// `H` for the head and the knot number for all others, like the puzzle description. Only
+    /// suitable for ropes of up to 10 knots.
+    Digits,
+    /// `H` for the head, the other knots shaded from dense to light towards the tail.
+    Gradient,
+}
+
+impl KnotStyle {
+    pub fn for_rope_length(rope_length: usize) -> Self {
+        if rope_length <= 10 {
+            KnotStyle::Digits
+        } else {
+            KnotStyle::Gradient
+        }
+    }
+
+    fn label(self, idx: usize, rope_length: usize) -> u8 {
+        match (self, idx) {
+            (_, 0) => b'H',
+            (KnotStyle::Digits, idx) => b'0' + (idx % 10) as u8,
+            (KnotStyle::Gradient, idx) => GRADIENT[(idx - 1) * GRADIENT.len() / (rope_length - 1)],
+        }
+    }
+}
+
+/// Draws the rope on the grid of `bounds`: knots in front cover the ones behind them, then the
+/// start `s`, then `#` for the cells the tail has visited. Ropes in space are seen from above.
+pub struct Renderer {
+    bounds: Bounds,
+    style: KnotStyle,
+}
+
+impl Renderer {
+    pub fn new(bounds: Bounds, style: KnotStyle) -> Self {
+        Self { bounds, style }
+    }
+
+    pub fn render(&self, knots: &[Pos3], tail_visited: &HashSet<Pos>) -> String {
+        let mut grid = self.render_tail_visited_grid(tail_visited);
+        for (idx, knot) in knots.iter().enumerate().rev() {
+            if let Some(cell) = self.bounds.index_of(planar(*knot)) {
+                grid[cell] = self.style.label(idx, knots.len());
+            }
+        }
+
+        self.grid_to_string(grid)
+    }
+
+    /// Only the start and the cells the tail has visited, like the puzzle's final picture.
+    pub fn render_tail_visited(&self, tail_visited: &HashSet<Pos>) -> String {
+        let grid = self.render_tail_visited_grid(tail_visited);
+        self.grid_to_string(grid)
+    }
+
+    fn render_tail_visited_grid(&self, tail_visited: &HashSet<Pos>) -> Vec<u8> {
+        let mut grid = vec![b'.'; self.bounds.width() * self.bounds.height()];
+        for pos in tail_visited {
+            if let Some(cell) = self.bounds.index_of(*pos) {
+                grid[cell] = b'#';
+            }
+        }
+        if let Some(cell) = self.bounds.index_of((0, 0)) {
+            grid[cell] = b's';
+        }
+        grid
+    }
+
+    fn grid_to_string(&self, grid: Vec<u8>) -> String {
+        let mut output = String::with_capacity(grid.len() + self.bounds.height());
+        for row in grid.chunks(self.bounds.width()) {
+            output.push_str(std::str::from_utf8(row).unwrap());
+            output.push('\n');
+        }
+        output
+    }
+}
+
+/// Calls `on_frame` with the frame after every step, followed by the final picture of the
+/// positions the tail has visited.
+fn render_frames(instructions: &[(Direction, u32)], rope: &Rope, mut on_frame: impl FnMut(String)) {
+    let renderer = Renderer::new(
+        Bounds::of_simulation(instructions, rope),
+        KnotStyle::for_rope_length(rope.knots().len()),
+    );
+    let mut tail_visited = HashSet::from([(0, 0)]);
+
+    simulate(instructions, rope.clone(), |rope| {
+        tail_visited.insert(planar(rope.tail()));
+        on_frame(renderer.render(rope.knots(), &tail_visited));
+    });
+    on_frame(renderer.render_tail_visited(&tail_visited));
+}
+
+/// Plays the simulation as a terminal animation by redrawing the screen with ANSI escape codes.
+pub fn animate(
+    instructions: &[(Direction, u32)],
+    rope: &Rope,
+    delay: Duration,
+    mut out: impl Write,
+) -> io::Result<()> {
+    let mut result = Ok(());
+    render_frames(instructions, rope, |frame| {
+        if result.is_ok() {
+            result = write!(out, "\x1b[H\x1b[2J{}", frame).and_then(|_| out.flush());
+            thread::sleep(delay);
+        }
+    });
+    result
+}
+
+/// Writes every frame to `frame_<step>.txt` in `dir`, the final picture of the tail's visited
+/// positions to `tail_visited.txt`. Returns the number of frames.
+pub fn write_frames(
+    instructions: &[(Direction, u32)],
+    rope: &Rope,
+    dir: &Path,
+) -> io::Result<usize> {
+    fs::create_dir_all(dir)?;
+    let step_count = instructions.iter().map(|(_, steps)| *steps as usize).sum();
+
+    let mut frame_idx = 0;
+    let mut result = Ok(());
+    render_frames(instructions, rope, |frame| {
+        if result.is_err() {
+            return;
+        }
+
+        frame_idx += 1;
+        let file_name = if frame_idx > step_count {
+            String::from("tail_visited.txt")
+        } else {
+            format!("frame_{:06}.txt", frame_idx)
+        };
+        result = fs::write(dir.join(file_name), frame);
+    });
+
+    result.map(|_| step_count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_instructions;
+
+    #[test]
+    fn test_bounds_of_simulation() {
+        let instructions = parse_instructions(include_str!("../test2.txt"));
+
+        let bounds = Bounds::of_simulation(&instructions, &Rope::new(10));
+
+        assert_eq!((-11, -15), bounds.min);
+        assert_eq!((14, 5), bounds.max);
+        assert_eq!((26, 21), (bounds.width(), bounds.height()));
+    }
+
+    #[test]
+    fn test_render_knots_in_front_cover_the_ones_behind() {
+        let instructions = parse_instructions(include_str!("../test1.txt"));
+        let renderer = Renderer::new(
+            Bounds::of_simulation(&instructions, &Rope::new(10)),
+            KnotStyle::Digits,
+        );
+        let mut rope = Rope::new(10);
+        for direction in [Direction::Right; 4] {
+            rope.step(direction);
+        }
+
+        let got = renderer.render(rope.knots(), &HashSet::new());
+
+        // The final state of "R 4" from the puzzle description.
+        let want = "......\n......\n......\n......\n4321H.\n";
+        assert_eq!(want, got);
+    }
+
+    #[test]
+    fn test_render_tail_visited() {
+        let instructions = parse_instructions(include_str!("../test1.txt"));
+        let renderer = Renderer::new(
+            Bounds::of_simulation(&instructions, &Rope::new(2)),
+            KnotStyle::Digits,
+        );
+        let mut tail_visited = HashSet::from([(0, 0)]);
+        simulate(&instructions, Rope::new(2), |rope| {
+            tail_visited.insert(planar(rope.tail()));
+        });
+
+        let want = "..##..\n...##.\n.####.\n....#.\ns###..\n";
+        assert_eq!(want, renderer.render_tail_visited(&tail_visited));
+    }
+
+    #[test]
+    fn test_gradient_labels() {
+        let labels: Vec<u8> = (0..25)
+            .map(|idx| KnotStyle::Gradient.label(idx, 25))
+            .collect();
+
+        assert_eq!(b'H', labels[0]);
+        assert_eq!(GRADIENT[0], labels[1]);
+        assert_eq!(GRADIENT[GRADIENT.len() - 1], labels[24]);
+    }
+
+    #[test]
+    fn test_write_frames() {
+        let dir = std::env::temp_dir().join(format!("day9-frames-{}", std::process::id()));
+        let instructions = parse_instructions(include_str!("../test1.txt"));
+
+        let frame_count = write_frames(&instructions, &Rope::new(2), &dir).unwrap();
+
+        assert_eq!(24, frame_count);
+        assert!(dir.join("frame_000024.txt").exists());
+        assert!(!dir.join("frame_000025.txt").exists());
+        assert_eq!(
+            "..##..\n...##.\n.####.\n....#.\ns###..\n",
+            fs::read_to_string(dir.join("tail_visited.txt")).unwrap()
+        );
+
+        // With a slack of 4, the tail of "R 4 / U 4" never leaves the start.
+        let instructions = parse_instructions("R 4\nU 4\n");
+        write_frames(&instructions, &Rope::new(2).with_slack(4), &dir).unwrap();
+        assert_eq!(
+            "....H\n.....\n.....\n.....\n1....\n",
+            fs::read_to_string(dir.join("frame_000008.txt")).unwrap()
+        );
+        assert_eq!(
+            ".....\n.....\n.....\n.....\ns....\n",
+            fs::read_to_string(dir.join("tail_visited.txt")).unwrap()
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
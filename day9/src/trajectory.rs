@@ -0,0 +1,224 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::{planar, simulate, Bounds, Direction, Pos, Pos3, Rope};
+
+/// The positions of every knot after every step of a simulation, step 0 being the start.
+///
+/// A knot only ever follows the knots in front of it, so the first `k` knots of a long rope move
+/// exactly like a rope of `k` knots. A single recording of a long rope therefore answers the
+/// puzzle for all shorter ropes as well.
+pub struct Trajectory {
+    rope_length: usize,
+    // Indexed by `step * rope_length + knot`.
+    positions: Vec<Pos3>,
+}
+
+impl Trajectory {
+    /// Runs the simulation with a copy of `rope`.
+    pub fn record(instructions: &[(Direction, u32)], rope: &Rope) -> Self {
+        let rope_length = rope.knots().len();
+        let mut positions = rope.knots().to_vec();
+
+        simulate(instructions, rope.clone(), |rope| {
+            positions.extend_from_slice(rope.knots());
+        });
+
+        Self {
+            rope_length,
+            positions,
+        }
+    }
+
+    pub fn rope_length(&self) -> usize {
+        self.rope_length
+    }
+
+    /// The number of recorded steps, not counting the start.
+    pub fn step_count(&self) -> usize {
+        self.positions.len() / self.rope_length - 1
+    }
+
+    pub fn position(&self, step: usize, knot: usize) -> Pos3 {
+        self.positions[step * self.rope_length + knot]
+    }
+
+    pub fn knot_path(&self, knot: usize) -> impl Iterator<Item = Pos3> + '_ {
+        self.positions
+            .iter()
+            .skip(knot)
+            .step_by(self.rope_length)
+            .copied()
+    }
+
+    /// The number of distinct positions `knot` has been at, which is the puzzle's answer for a
+    /// rope of `knot + 1` knots.
+    pub fn visited_position_count(&self, knot: usize) -> usize {
+        self.knot_path(knot).collect::<HashSet<_>>().len()
+    }
+
+    /// How often `knot` entered each position on the plane. The start counts as the first
+    /// visit, standing still does not count as another one, and neither does moving only
+    /// forward or backward.
+    pub fn visit_counts(&self, knot: usize) -> HashMap<Pos, u32> {
+        let mut visit_counts = HashMap::new();
+        let mut previous: Option<Pos> = None;
+
+        for pos in self.knot_path(knot).map(planar) {
+            if previous != Some(pos) {
+                *visit_counts.entry(pos).or_insert(0) += 1;
+            }
+            previous = Some(pos);
+        }
+
+        visit_counts
+    }
+
+    /// The area on the plane covered by all knots over the whole simulation.
+    pub fn bounds(&self) -> Bounds {
+        let mut bounds = Bounds {
+            min: (0, 0),
+            max: (0, 0),
+        };
+        self.positions
+            .iter()
+            .for_each(|pos| bounds.include(planar(*pos)));
+        bounds
+    }
+
+    /// One `step,knot,x,y,z` row per knot and step, with a header.
+    pub fn write_csv(&self, out: impl Write) -> io::Result<()> {
+        let mut out = BufWriter::new(out);
+        writeln!(out, "step,knot,x,y,z")?;
+        for (idx, (x, y, z)) in self.positions.iter().enumerate() {
+            writeln!(
+                out,
+                "{},{},{},{},{}",
+                idx / self.rope_length,
+                idx % self.rope_length,
+                x,
+                y,
+                z
+            )?;
+        }
+        out.flush()
+    }
+
+    /// Binary PGM (P5) of the visit counts of `knot` over the area of the whole simulation.
+    /// Unvisited cells are black, visited ones range from dark gray for a single visit to white
+    /// for `max_visits`. Passing the same `max_visits` for all knots keeps the maps comparable.
+    pub fn write_visit_heat_map_pgm(
+        &self,
+        knot: usize,
+        max_visits: u32,
+        mut out: impl Write,
+    ) -> io::Result<()> {
+        let bounds = self.bounds();
+        let mut pixels = vec![0u8; bounds.width() * bounds.height()];
+        for (pos, visits) in self.visit_counts(knot) {
+            let cell = bounds.index_of(pos).unwrap();
+            pixels[cell] = 64 + (191 * visits.min(max_visits) / max_visits.max(1)) as u8;
+        }
+
+        write!(out, "P5\n{} {}\n255\n", bounds.width(), bounds.height())?;
+        out.write_all(&pixels)
+    }
+
+    /// Writes `knot_<idx>.pgm` for every knot to `dir`, all shaded relative to the most visited
+    /// cell of any knot.
+    pub fn write_visit_heat_maps(&self, dir: &Path) -> io::Result<()> {
+        fs::create_dir_all(dir)?;
+        let max_visits = (0..self.rope_length)
+            .filter_map(|knot| self.visit_counts(knot).into_values().max())
+            .max()
+            .unwrap_or(1);
+
+        for knot in 0..self.rope_length {
+            let file = fs::File::create(dir.join(format!("knot_{:04}.pgm", knot)))?;
+            self.write_visit_heat_map_pgm(knot, max_visits, BufWriter::new(file))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{count_visited_tail_pos, parse_instructions};
+
+    #[test]
+    fn test_long_rope_answers_shorter_ropes() {
+        let instructions = parse_instructions(include_str!("../input.txt"));
+
+        let trajectory = Trajectory::record(&instructions, &Rope::new(10));
+
+        assert_eq!(6266, trajectory.visited_position_count(1));
+        assert_eq!(2369, trajectory.visited_position_count(9));
+    }
+
+    #[test]
+    fn test_record_with_slack() {
+        let instructions = parse_instructions(include_str!("../input.txt"));
+
+        let trajectory = Trajectory::record(&instructions, &Rope::new(10).with_slack(3));
+
+        for length in [2, 10] {
+            assert_eq!(
+                count_visited_tail_pos(&instructions, Rope::new(length).with_slack(3)) as usize,
+                trajectory.visited_position_count(length - 1)
+            );
+        }
+    }
+
+    #[test]
+    fn test_positions_and_visit_counts() {
+        let instructions = parse_instructions("R 2\nL 2\nR 1\n");
+
+        let trajectory = Trajectory::record(&instructions, &Rope::new(2));
+
+        assert_eq!(5, trajectory.step_count());
+        assert_eq!((2, 0, 0), trajectory.position(2, 0));
+        assert_eq!((1, 0, 0), trajectory.position(2, 1));
+        assert_eq!(
+            vec![0, 0, 1, 1, 1, 1],
+            trajectory
+                .knot_path(1)
+                .map(|(x, _, _)| x)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            HashMap::from([((0, 0), 2), ((1, 0), 3), ((2, 0), 1)]),
+            trajectory.visit_counts(0)
+        );
+    }
+
+    #[test]
+    fn test_write_csv() {
+        let instructions = parse_instructions("R 2\n");
+        let trajectory = Trajectory::record(&instructions, &Rope::new(2));
+
+        let mut csv = Vec::new();
+        trajectory.write_csv(&mut csv).unwrap();
+
+        let want =
+            "step,knot,x,y,z\n0,0,0,0,0\n0,1,0,0,0\n1,0,1,0,0\n1,1,0,0,0\n2,0,2,0,0\n2,1,1,0,0\n";
+        assert_eq!(want, String::from_utf8(csv).unwrap());
+    }
+
+    #[test]
+    fn test_write_visit_heat_map_pgm() {
+        let instructions = parse_instructions("R 2\nL 2\nR 1\n");
+        let trajectory = Trajectory::record(&instructions, &Rope::new(2));
+
+        let mut pgm = Vec::new();
+        trajectory.write_visit_heat_map_pgm(0, 3, &mut pgm).unwrap();
+
+        let mut want = b"P5\n3 1\n255\n".to_vec();
+        // (0, 0) is entered twice, (1, 0) three times and (2, 0) once.
+        want.extend_from_slice(&[191, 255, 127]);
+        assert_eq!(want, pgm);
+    }
+}